@@ -3,6 +3,27 @@ import "./main.css"
 const verticalDemos = window.matchMedia("(max-aspect-ratio: 1/1)").matches;
 const dimensions = verticalDemos ? [900, 1600] : [1600, 900];
 
+// Each demo draws its sites from a 64-bit seed, which is logged so that a layout can be reproduced
+// by adding it to the page's address, like ?demo-2-seed=1234. Seeds are passed to the demos as a
+// BigInt, since they don't fit in a Number.
+const params = new URLSearchParams(window.location.search);
+function seed(demo) {
+  let seed = null;
+  try {
+    seed = params.has(`${demo}-seed`) ? BigInt.asUintN(64, BigInt(params.get(`${demo}-seed`))) : null;
+  } catch {
+    console.warn(`${demo} seed is not a whole number, using a random one`);
+  }
+
+  if (seed === null) {
+    const [high, low] = crypto.getRandomValues(new Uint32Array(2));
+    seed = (BigInt(high) << 32n) | BigInt(low);
+  }
+
+  console.log(`${demo} seed: ${seed}`);
+  return seed;
+}
+
 
 function demo1(wasm) {
   const canvas = document.getElementById("demo-1");
@@ -17,10 +38,10 @@ function demo1(wasm) {
 
   regenerate.addEventListener("click", () => {
     context.clearRect(0, 0, canvas.width, canvas.height);
-    wasm.demo1(canvas, +points.value);
+    wasm.demo1(canvas, +points.value, seed("demo-1"));
   });
 
-  wasm.demo1(canvas, +points.value);
+  wasm.demo1(canvas, +points.value, seed("demo-1"));
 }
 
 function demo2(wasm) {
@@ -34,14 +55,16 @@ function demo2(wasm) {
 
   const go = document.getElementById("demo-2-go");
   const points = document.getElementById("demo-2-points");
+  const metric = document.getElementById("demo-2-metric");
+  const method = document.getElementById("demo-2-method");
 
   go.addEventListener("click", () => {
     context.clearRect(0, 0, canvas.width, canvas.height);
-    wasm.demo2(canvas, +points.value);
+    wasm.demo2(canvas, +points.value, metric.value, method.value, seed("demo-2"));
   });
 
 
-  wasm.demo2(canvas, +points.value);
+  wasm.demo2(canvas, +points.value, metric.value, method.value, seed("demo-2"));
 }
 
 function demo3(wasm) {
@@ -57,11 +80,11 @@ function demo3(wasm) {
 
   go.addEventListener("click", () => {
     context.clearRect(0, 0, canvas.width, canvas.height);
-    wasm.demo3(canvas, +points.value);
+    wasm.demo3(canvas, +points.value, seed("demo-3"));
   });
 
 
-  wasm.demo3(canvas, +points.value);
+  wasm.demo3(canvas, +points.value, seed("demo-3"));
 }
 
 function demo4(wasm) {
@@ -77,18 +100,31 @@ function demo4(wasm) {
 
   go.addEventListener("click", () => {
     context.clearRect(0, 0, canvas.width, canvas.height);
-    wasm.demo4(canvas, +points.value);
+    wasm.demo4(canvas, +points.value, seed("demo-4"));
   });
 
 
-  wasm.demo4(canvas, +points.value);
+  wasm.demo4(canvas, +points.value, seed("demo-4"));
 }
 
 function demo5(wasm) {
+  const canvas = document.getElementById("demo-5");
+
+  canvas.width = dimensions[0];
+  canvas.height = dimensions[1];
 
-  // For mobile screens, flip the canvas to portrait mode, and go full screen.
+  const context = canvas.getContext("2d");
+
+  const go = document.getElementById("demo-5-go");
+  const points = document.getElementById("demo-5-points");
+
+  go.addEventListener("click", () => {
+    context.clearRect(0, 0, canvas.width, canvas.height);
+    wasm.demo5(canvas, +points.value, seed("demo-5"));
+  });
 
 
+  wasm.demo5(canvas, +points.value, seed("demo-5"));
 };
 
 
@@ -110,6 +146,7 @@ import("./pkg")
     // Demo 3: Finds and draws all of the perpendicular bisectors, limiting their range appropriately
     demo4(wasm);
 
+    // Demo 5: Computes the diagram with Fortune's algorithm, and draws the cells
     demo5(wasm);
   
 
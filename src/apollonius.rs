@@ -0,0 +1,435 @@
+//!
+//! Apollonius diagrams (additively weighted Voronoi diagrams). Each site is a circle, with its
+//! weight as the radius, and the distance from a point to a site is `|p - center| - weight`: the
+//! distance to the circle itself. The edges between cells are branches of hyperbolas, with the two
+//! centers as their foci. The dual of the diagram, joining sites whose cells share an edge, is the
+//! Apollonius graph.
+//!
+//! A site whose circle is inside another site's circle is always further away than the bigger
+//! site, so it is hidden: it has no cell at all. Every other cell is star-shaped around its center,
+//! so it can be described by how far it reaches in each direction. Looking out from the center of
+//! site s, in the direction u, the edge with another site t is at a distance of
+//!
+//!   ρ(u) = (|d|² - a²) / (2 (u·d - a))
+//!
+//! where d is the offset from s to t, and a is the weight of s minus the weight of t. This is the
+//! polar equation of a hyperbola from its focus. Where the denominator isn't positive, t never
+//! gets closer than s. The cell reaches as far as the nearest of these in each direction, and the
+//! directions where two of them meet can be solved for exactly, so each cell is found as a lower
+//! envelope of hyperbolas, and only sampled into polylines at the end.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use std::f64::consts::PI;
+use crate::error::{validate_weighted_sites, VoronoiError};
+use crate::grid::Grid;
+use crate::point::{Point, WeightedPoint};
+use crate::polygon::ConvexPolygon;
+
+/// The most times an arc is halved while sampling it
+const MAX_DEPTH: usize = 16;
+
+/// The largest angle, seen from a site, between two samples of its cell
+const MAX_STEP: f64 = PI / 16.0;
+
+/// An edge of the diagram between the cells of two sites, sampled into a polyline. The points are
+/// on the hyperbola (apart from ends cut off by the region), and the polyline is never further
+/// than the sampling tolerance from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arc {
+    /// The two sites, in increasing order
+    pub sites: [usize; 2],
+
+    pub points: Vec<Point>,
+}
+
+/// The Apollonius diagram of a set of sites, clipped to a region
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApolloniusDiagram {
+    pub sites: Vec<WeightedPoint>,
+
+    /// The cell of each site, as a polygon sampled along its edges, with its vertices
+    /// counter-clockwise. Cells of hidden sites, and cells entirely outside the region, are empty.
+    pub cells: Vec<Vec<Point>>,
+
+    /// The edges between cells, within the region. Two cells can share more than one edge.
+    pub arcs: Vec<Arc>,
+
+    /// True for sites that have no cell, because they are inside another site's circle
+    pub hidden: Vec<bool>,
+}
+
+impl ApolloniusDiagram {
+    /// Builds the diagram within a region. Arcs are sampled finely enough that they are never
+    /// further than `tolerance` from the true hyperbolas.
+    ///
+    /// Fails if there are no sites, a coordinate or weight is out of range, or two sites have the
+    /// same center.
+    pub fn new(sites: &[WeightedPoint], region: &ConvexPolygon, tolerance: f64) -> Result<ApolloniusDiagram, VoronoiError> {
+        validate_weighted_sites(sites)?;
+
+        let centers: Vec<Point> = sites.iter().map(|site| site.point).collect();
+        let grid = Grid::new(&centers);
+        let heaviest = sites.iter().map(|site| site.weight).fold(f64::NEG_INFINITY, f64::max);
+        let tolerance = tolerance.max(1e-12 * region.extent());
+
+        let hidden: Vec<bool> = (0..sites.len()).map(|s| is_hidden(s, sites, &grid, heaviest)).collect();
+
+        let mut cells = Vec::with_capacity(sites.len());
+        let mut arcs = Vec::new();
+        for s in 0..sites.len() {
+            if hidden[s] {
+                cells.push(Vec::new());
+                continue;
+            }
+
+            // Cells are cut off far enough out that the cut is entirely outside the region
+            let center = sites[s].point;
+            let cap = 2.0 * region
+                .vertices()
+                .iter()
+                .map(|c| (c.x - center.x).hypot(c.y - center.y))
+                .fold(0.0, f64::max);
+
+            let envelope = Envelope::of(s, sites, &hidden, &grid, heaviest, cap);
+
+            let cell = envelope.sample(center, envelope.pieces[0].from, envelope.pieces[0].from + 2.0 * PI, tolerance);
+            cells.push(region.clip(&cell[..cell.len() - 1], 1e-10 * region.extent()));
+
+            // Each arc is on the envelopes of both its sites, so it only comes from the first
+            for piece in envelope.pieces.iter() {
+                let t = match piece.bound.owner {
+                    Some(t) if s < t => t,
+                    _ => continue,
+                };
+
+                let polyline = envelope.sample(center, piece.from, piece.to, tolerance);
+                for points in region.clip_polyline(&polyline) {
+                    arcs.push(Arc { sites: [s, t], points });
+                }
+            }
+        }
+
+        Ok(ApolloniusDiagram {
+            sites: sites.to_vec(),
+            cells,
+            arcs,
+            hidden,
+        })
+    }
+
+    /// The sites whose cells share an edge with the cell of a site, within the region. These are
+    /// the neighbors of the site in the Apollonius graph.
+    pub fn neighbors(&self, site: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut neighbors: Vec<usize> = self
+            .arcs
+            .iter()
+            .filter_map(|arc| match arc.sites {
+                [s, t] if s == site => Some(t),
+                [s, t] if t == site => Some(s),
+                _ => None,
+            })
+            .collect();
+
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors.into_iter()
+    }
+}
+
+/// Returns true if the circle of site s is inside the circle of another site. Only sites within
+/// the difference between the heaviest weight and this site's weight can contain it.
+fn is_hidden(s: usize, sites: &[WeightedPoint], grid: &Grid, heaviest: f64) -> bool {
+    let site = sites[s];
+    let furthest = heaviest - site.weight;
+
+    for ring in 0.. {
+        if (ring as f64 - 1.0) * grid.size > furthest || !grid.has_ring(site.point, ring) {
+            return false;
+        }
+
+        let contained = grid.ring(site.point, ring).any(|t| {
+            let other = sites[t];
+            let distance = (other.point.x - site.point.x).hypot(other.point.y - site.point.y);
+
+            t != s && distance <= other.weight - site.weight
+        });
+        if contained {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// How far a cell reaches in the direction at angle θ, `ρ(u) = k / (2 (u·d - a))` for u at angle θ,
+/// or infinity where the denominator isn't positive. Bounds with no owner are the cap, which is a
+/// circle around the site.
+#[derive(Debug, Clone, Copy)]
+struct Bound {
+    owner: Option<usize>,
+    d: (f64, f64),
+    a: f64,
+    k: f64,
+}
+
+impl Bound {
+    /// The edge between site s and site t, seen from s
+    fn between(s: WeightedPoint, t: WeightedPoint, owner: usize) -> Bound {
+        let d = (t.point.x - s.point.x, t.point.y - s.point.y);
+        let a = s.weight - t.weight;
+
+        Bound {
+            owner: Some(owner),
+            d,
+            a,
+            k: d.0 * d.0 + d.1 * d.1 - a * a,
+        }
+    }
+
+    /// A circle of the given radius, which has a constant denominator of 1
+    fn cap(radius: f64) -> Bound {
+        Bound {
+            owner: None,
+            d: (0.0, 0.0),
+            a: -1.0,
+            k: 2.0 * radius,
+        }
+    }
+
+    fn at(&self, theta: f64) -> f64 {
+        let (sin, cos) = theta.sin_cos();
+        let denominator = cos * self.d.0 + sin * self.d.1 - self.a;
+
+        if denominator > 0.0 {
+            self.k / (2.0 * denominator)
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    /// The angles where two bounds meet, in [0, 2π). Multiplying out the denominators leaves an
+    /// equation `u·v = c`, which has up to two solutions.
+    fn crossings(&self, other: &Bound) -> Vec<f64> {
+        let v = (self.k * other.d.0 - other.k * self.d.0, self.k * other.d.1 - other.k * self.d.1);
+        let c = self.k * other.a - other.k * self.a;
+
+        let length = v.0.hypot(v.1);
+        if length == 0.0 || c.abs() > length {
+            return Vec::new();
+        }
+
+        let (base, spread) = (v.1.atan2(v.0), (c / length).acos());
+        [base - spread, base + spread]
+            .iter()
+            .map(|&theta| theta.rem_euclid(2.0 * PI))
+            .filter(|&theta| self.at(theta).is_finite() && other.at(theta).is_finite())
+            .collect()
+    }
+
+    /// The furthest this bound reaches between two angles
+    fn reach(&self, from: f64, to: f64) -> f64 {
+        let mut reach = self.at(from).max(self.at(to));
+
+        // The nearest point of the hyperbola to the focus is away from the other site, where
+        // u·d is smallest, and it only gets further from there
+        let furthest = (-self.d.1).atan2(-self.d.0).rem_euclid(2.0 * PI);
+        for theta in [furthest, furthest + 2.0 * PI] {
+            if from < theta && theta < to {
+                reach = reach.max(self.at(theta));
+            }
+        }
+
+        reach
+    }
+}
+
+/// A piece of the envelope, from one angle to another, where the same bound is nearest
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    from: f64,
+    to: f64,
+    bound: Bound,
+}
+
+/// How far the cell of a site reaches in every direction. The pieces are in order, and go once
+/// around the site without gaps, though they can start at any angle.
+#[derive(Debug, Clone)]
+struct Envelope {
+    pieces: Vec<Piece>,
+}
+
+impl Envelope {
+    /// Finds the envelope of site s, cut off at the cap
+    fn of(s: usize, sites: &[WeightedPoint], hidden: &[bool], grid: &Grid, heaviest: f64, cap: f64) -> Envelope {
+        let site = sites[s];
+        let mut envelope = Envelope {
+            pieces: vec![Piece { from: 0.0, to: 2.0 * PI, bound: Bound::cap(cap) }],
+        };
+        let mut reach = cap;
+
+        // Visit the other sites from nearest to furthest, a ring of buckets at a time. A point
+        // within the reach of the cell is at least `distance - reach - weight` from another site,
+        // and at most `reach - site.weight` from this one, so further sites can't change the cell.
+        for ring in 0.. {
+            let nearest = (ring as f64 - 1.0) * grid.size;
+            if nearest >= 2.0 * reach + heaviest - site.weight || !grid.has_ring(site.point, ring) {
+                break;
+            }
+
+            for t in grid.ring(site.point, ring) {
+                if t == s || hidden[t] {
+                    continue;
+                }
+
+                let other = sites[t];
+                let distance = (other.point.x - site.point.x).hypot(other.point.y - site.point.y);
+                if distance - other.weight + site.weight < 2.0 * reach {
+                    envelope.lower(Bound::between(site, other, t));
+                }
+            }
+
+            reach = envelope.reach();
+        }
+
+        // Join the piece across angle 0 into one
+        let last = envelope.pieces.len() - 1;
+        if last > 0 && envelope.pieces[0].bound.owner == envelope.pieces[last].bound.owner {
+            let first = envelope.pieces.remove(0);
+            envelope.pieces[last - 1].to = first.to + 2.0 * PI;
+        }
+
+        envelope
+    }
+
+    /// Lowers the envelope to a new bound, wherever it is nearer
+    fn lower(&mut self, bound: Bound) {
+        let mut lowered: Vec<Piece> = Vec::with_capacity(self.pieces.len() + 2);
+
+        for piece in self.pieces.iter() {
+            let mut cuts = bound.crossings(&piece.bound);
+            cuts.retain(|&theta| piece.from < theta && theta < piece.to);
+            cuts.sort_by(|a, b| a.total_cmp(b));
+
+            let mut from = piece.from;
+            for to in cuts.into_iter().chain(std::iter::once(piece.to)) {
+                let middle = (from + to) / 2.0;
+                let nearest = if bound.at(middle) < piece.bound.at(middle) { bound } else { piece.bound };
+
+                match lowered.last_mut() {
+                    Some(last) if last.bound.owner == nearest.owner => last.to = to,
+                    _ => lowered.push(Piece { from, to, bound: nearest }),
+                }
+                from = to;
+            }
+        }
+
+        self.pieces = lowered;
+    }
+
+    /// The furthest the cell reaches in any direction
+    fn reach(&self) -> f64 {
+        self.pieces
+            .iter()
+            .map(|piece| piece.bound.reach(piece.from, piece.to))
+            .fold(0.0, f64::max)
+    }
+
+    /// Samples the edge of the cell from one angle to another, around a center. Both angles
+    /// have to be within the range of the pieces. The first and last points are exactly at those
+    /// angles, and the corners between pieces are always included.
+    fn sample(&self, center: Point, from: f64, to: f64, tolerance: f64) -> Vec<Point> {
+        let mut points = Vec::new();
+
+        for piece in self.pieces.iter().filter(|piece| piece.to > from && piece.from < to) {
+            let point = |theta: f64| {
+                let (sin, cos) = theta.sin_cos();
+                let rho = piece.bound.at(theta);
+                Point::new(center.x + rho * cos, center.y + rho * sin)
+            };
+
+            let (start, end) = (piece.from.max(from), piece.to.min(to));
+            if points.is_empty() {
+                points.push(point(start));
+            }
+
+            // Each piece is split evenly, then halved until it is close enough to the curve. The
+            // cap is always outside the region, so it only needs to be roughly round.
+            let steps = ((end - start) / MAX_STEP).ceil().max(1.0) as usize;
+            for i in 0..steps {
+                let a = start + (end - start) * i as f64 / steps as f64;
+                let b = start + (end - start) * (i + 1) as f64 / steps as f64;
+
+                if piece.bound.owner.is_some() {
+                    refine(&point, a, b, tolerance, 0, &mut points);
+                } else {
+                    points.push(point(b));
+                }
+            }
+        }
+
+        points
+    }
+}
+
+/// Adds points between two angles until the polyline is within the tolerance of the curve, ending
+/// with the point at angle b
+fn refine<P: Fn(f64) -> Point>(point: &P, a: f64, b: f64, tolerance: f64, depth: usize, points: &mut Vec<Point>) {
+    let (p, q, middle) = (point(a), point(b), point((a + b) / 2.0));
+
+    // The distance from the middle of the curve to the chord between its ends
+    let (dx, dy) = (q.x - p.x, q.y - p.y);
+    let length = dx.hypot(dy);
+    let error = if length > 0.0 {
+        ((middle.x - p.x) * dy - (middle.y - p.y) * dx).abs() / length
+    } else {
+        (middle.x - p.x).hypot(middle.y - p.y)
+    };
+
+    if error > tolerance && depth < MAX_DEPTH {
+        refine(point, a, (a + b) / 2.0, tolerance, depth + 1, points);
+        refine(point, (a + b) / 2.0, b, tolerance, depth + 1, points);
+    } else {
+        points.push(q);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use crate::generators::{seeded_rng, uniform};
+    use crate::polygon::contains;
+
+    #[test]
+    fn points_are_in_the_cell_of_their_nearest_site() {
+        let region = ConvexPolygon::rectangle(0.0, 0.0, 1000.0, 1000.0).unwrap();
+        let tolerance = 0.01;
+
+        for seed in 0..10 {
+            let mut rng = seeded_rng(seed);
+            let sites: Vec<WeightedPoint> = uniform(&mut rng, 60, 0.0..1000.0, 0.0..1000.0)
+                .into_iter()
+                .map(|p| WeightedPoint::new(p.x, p.y, rng.gen_range(0.0..80.0)))
+                .collect();
+            let diagram = ApolloniusDiagram::new(&sites, &region, tolerance).unwrap();
+
+            for p in uniform(&mut rng, 2000, 0.0..1000.0, 0.0..1000.0) {
+                let mut distances: Vec<(f64, usize)> =
+                    sites.iter().enumerate().map(|(i, site)| (site.additive_distance(p), i)).collect();
+                distances.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                // The cells are sampled, so points within the tolerance of an edge could go either
+                // way. Moving a point changes the difference of two distances at most twice as much.
+                if distances[1].0 - distances[0].0 < 4.0 * tolerance {
+                    continue;
+                }
+
+                let nearest = distances[0].1;
+                assert!(!diagram.hidden[nearest], "{} is nearest to hidden site {}", p, nearest);
+                assert!(contains(&diagram.cells[nearest], p), "{} is not in the cell of its nearest site {}", p, nearest);
+            }
+        }
+    }
+}
@@ -1,81 +1,65 @@
-///
-/// Demo 1: Cursor Point
-/// 
-/// Generates a number of voronoi sites, and as the mouse (or finger) moves around the canvas, draws
-/// a line from the cursor to the voronoi site nearest to the cursor.
-/// 
-/// Author: Brendan McGuire
-/// Date: 18 July 2021
-/// 
-/// https://bren.app/voronoi/
-/// 
-
-
-use std::{cmp::Ordering};
-use crate::point::{Point, Metric};
+//!
+//! Demo 1: Cursor Point
+//! 
+//! Generates a number of voronoi sites, and as the mouse (or finger) moves around the canvas, draws
+//! a line from the cursor to the voronoi site nearest to the cursor.
+//! 
+//! Author: Brendan McGuire
+//! Date: 18 July 2021
+//! 
+//! https://bren.app/voronoi/
+//! 
+
+
+#[cfg(feature = "wasm")]
+use crate::console_log;
+use crate::error::VoronoiError;
+use crate::metric::{nearest_site, Euclidean};
+use crate::point::Point;
+use crate::render::Renderer;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::{JsCast};
-use web_sys::Touch;
+#[cfg(feature = "wasm")]
 use super::demo_setup;
+use super::draw_sites;
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
-pub fn demo1(canvas: web_sys::HtmlCanvasElement, num_points: u32) -> Result<(), JsValue> {
+pub fn demo1(canvas: web_sys::HtmlCanvasElement, num_points: u32, seed: u64) -> Result<(), JsValue> {
     
     // Basic setup
-    let (context, sites) = demo_setup(&canvas, num_points);
+    let (renderer, sites) = demo_setup(&canvas, num_points, seed)?;
 
     // Get the rendering dimensions of the canvas (1600x900). This is static, to make rendering much
     // much easier.
-    let width = canvas.width() as i32;
-    let height = canvas.height() as i32;
+    let width = canvas.width() as f64;
+    let height = canvas.height() as f64;
 
     // Get the actual dimensions of the canvas, which is based on the size of the device, and will
     // change. The ratio will always be the same (16:9)
     let rect = canvas.get_bounding_client_rect();
-    let canvas_width = rect.width() as i32;
-    let canvas_height = rect.height() as i32;
-    let canvas_left = rect.left();
-    let canvas_top = rect.top();
+    let canvas_width = rect.width();
+    let canvas_height = rect.height();
 
 
     // Copy points so it can be moved into the closure
     let points = sites.clone();
     {
-        let context = context.clone();
+        let mut renderer = renderer.clone();
         let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
             // Get the mouse position, in the rendering coordinates
             let (x, y) = (event.offset_x() as f64, event.offset_y() as f64);
             let (x, y) = (
-                x / canvas_width as f64 * width as f64,
-                y / canvas_height as f64 * height as f64,
+                x / canvas_width * width,
+                y / canvas_height * height,
             );
 
-            let mouse = Point::new(x as i32, y as i32);
-
-            context.clear_rect(0.0, 0.0, width.into(), height.into());
-
-            // Render each point as a little circle, and draw guidance lines to each
-            for point in &points {
-                let (x, y): (f64, f64) = (point.x.into(), point.y.into());
-
-                context.begin_path();
-                context
-                    .arc(x, y, 6.0, 0.0, std::f64::consts::PI * 2.0)
-                    .unwrap();
-                context.stroke();
-            }
-
-            let closest = points.iter().min_by(|a, b| {
-                a.dist(&mouse, Metric::Euclidean)
-                    .partial_cmp(&b.dist(&mouse, Metric::Euclidean))
-                    .unwrap_or(Ordering::Equal)
-            });
+            let mouse = Point::new(x, y);
 
-            if let Some(site) = closest {
-                context.begin_path();
-                context.move_to(site.x.into(), site.y.into());
-                context.line_to(mouse.x.into(), mouse.y.into());
-                context.stroke();
+            if let Err(error) = renderer.clear().and_then(|_| draw_nearest(&mut renderer, &points, mouse)) {
+                console_log!("{}", error);
             }
         }) as Box<dyn FnMut(_)>);
 
@@ -85,4 +69,15 @@ pub fn demo1(canvas: web_sys::HtmlCanvasElement, num_points: u32) -> Result<(),
 
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Draws the sites, and a line from the cursor to the nearest one
+pub fn draw_nearest<R: Renderer + ?Sized>(renderer: &mut R, sites: &[Point], cursor: Point) -> Result<(), VoronoiError> {
+    draw_sites(renderer, sites)?;
+
+    if let Some(site) = nearest_site(sites, cursor, &Euclidean).map(|i| sites[i]) {
+        renderer.polyline(&[site, cursor])?;
+    }
+
+    Ok(())
+}
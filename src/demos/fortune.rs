@@ -0,0 +1,41 @@
+//!
+//! Demo 5: Fortune's Algorithm
+//!
+//! Computes the Voronoi diagram of the sites with Fortune's algorithm, and draws the edges of every
+//! cell, clipped to the canvas.
+//!
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use crate::error::VoronoiError;
+#[cfg(feature = "wasm")]
+use crate::fortune::fortunes_algorithm;
+use crate::palette::ACCENT;
+use crate::polygon::ConvexPolygon;
+use crate::render::{self, Renderer, Style};
+use crate::voronoi::VoronoiDiagram;
+#[cfg(feature = "wasm")]
+use super::demo_setup;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn demo5(
+    canvas: web_sys::HtmlCanvasElement,
+    num_points: u32,
+    seed: u64,
+) -> Result<(), JsValue> {
+
+    let (mut renderer, sites) = demo_setup(&canvas, num_points, seed)?;
+    let diagram = fortunes_algorithm(&sites)?;
+    draw_cells(&mut renderer, &diagram, canvas.width() as f64, canvas.height() as f64)?;
+
+    Ok(())
+}
+
+/// Draws the outline of every cell, clipped to a `width` by `height` canvas
+pub fn draw_cells<R: Renderer + ?Sized>(renderer: &mut R, diagram: &VoronoiDiagram, width: f64, height: f64) -> Result<(), VoronoiError> {
+    // Clipping every cell to the canvas closes off the unbounded ones along its edges
+    let canvas_bounds = ConvexPolygon::rectangle(0.0, 0.0, width, height)?;
+
+    render::cells(renderer, diagram, &canvas_bounds, &Style::stroke(ACCENT, 3.0), &[])
+}
@@ -1,98 +1,90 @@
-///
-/// Demo 2: Naive
-/// 
-/// A naive approach to finding voronoi regions. It simply iterates through every pixel on the
-/// screen, and finds the closest site (as defined by euclidean squared distance) and marks each
-/// pixel with its closest site
-/// 
-
-use std::{rc::Rc};
-use crate::point::{Point, Metric};
+//!
+//! Demo 2: Naive
+//! 
+//! A naive approach to finding voronoi regions. It simply iterates through every pixel on the
+//! screen, and finds the closest site (as defined by the chosen metric) and marks each pixel with
+//! its closest site
+//! 
+
+use crate::error::VoronoiError;
+use crate::image::Image;
+#[cfg(feature = "wasm")]
+use crate::jfa::{jump_flooding, Variant};
+#[cfg(feature = "wasm")]
+use crate::metric::{Chebyshev, Euclidean, Manhattan, Metric, Minkowski};
+use crate::palette::{parse_color, COLORS};
+use crate::point::Point;
+use crate::raster::RasterVoronoi;
+use crate::render::{self, Renderer, Style};
+#[cfg(feature = "wasm")]
+use crate::render::canvas::CanvasRenderer;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
-use wasm_bindgen::{JsCast};
-
-
-const COLORS: &[&str] = &[
-    "#55efc4",
-    "#81ecec",
-    "#74b9ff",
-    "#dfe6e9",
-    "#ffeaa7",
-    "#fab1a0",
-    "#ff7675",
-    "#fd79a8",
-    "#636e72"
-];
+#[cfg(feature = "wasm")]
+use super::demo_sites;
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn demo2(
     canvas: web_sys::HtmlCanvasElement,
     num_points: u32,
+    metric: &str,
+    method: &str,
+    seed: u64,
 ) -> Result<(), JsValue> {
-    let mut rng = rand::thread_rng();
-    let context = canvas
-        .get_context("2d")?
-        .unwrap()
-        .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
-    let context = Rc::new(context);
+    let metric = metric_by_name(metric)?;
+    let mut renderer = CanvasRenderer::new(&canvas)?;
 
     // Get the rendering dimensions of the canvas (1600x900). This is static, to make rendering much
     // much easier.
-    let width = canvas.width() as i32;
-    let height = canvas.height() as i32;
+    let width = canvas.width();
+    let height = canvas.height();
 
     // Generate the initial points to base each region off of
-    let sites: Vec<Point> = (0..num_points)
-        .map(|_| Point::random(&mut rng, 0..width, 0..height))
-        .collect();
-
-    // Set stroke width and style
-    context.set_stroke_style(&JsValue::from_str("#fab1a0"));
-    context.set_fill_style(&JsValue::from_str("#fab1a0"));
-    context.set_line_width(4.0);
-    let dash = JsValue::from_serde(&[0.0]).unwrap();
-    context.set_line_dash(&dash).expect("set_line_dash");
-
-    // For each pixel, find the closest site, and color it appropriately
-    for x in 0..width {
-        for y in 0..height {
-            let point = Point::new(x, y);
+    let sites = demo_sites(num_points, seed, width as f64, height as f64);
+
+    // Label every pixel with its closest site (exactly, or approximately with jump flooding), then
+    // draw them all at once
+    let raster = match method {
+        "exact" => RasterVoronoi::new(width, height, sites.len(), |i, p| metric.distance(sites[i], p)),
+        "jfa" => jump_flooding(width, height, &sites, &metric, Variant::Jfa),
+        "1+jfa" => jump_flooding(width, height, &sites, &metric, Variant::OnePlusJfa),
+        "jfa+2" => jump_flooding(width, height, &sites, &metric, Variant::JfaPlusTwo),
+        _ => return Err(js_sys::Error::new(&format!("Unknown method {}", method)).into()),
+    };
+    draw_raster(&mut renderer, &raster, &sites)?;
 
-            let mut closest_distance = f64::INFINITY;
-            let mut closest = None;
-            for (i, site) in sites.iter().enumerate() {
-                let dist = site.dist(&point, Metric::EuclideanSquared);
-
-                if dist < closest_distance {
-                    closest_distance = dist;
-                    closest = Some(i);
-                }
-
-            }
+    Ok(())
+}
 
-            if let Some(i) = closest {
-                context.set_fill_style(&JsValue::from_str(COLORS[i]));
-                context.fill_rect(x.into(), y.into(), 1.0, 1.0);
-            }
+/// Draws each pixel in the color of its site, then the sites as little dark circles
+pub fn draw_raster<R: Renderer + ?Sized>(renderer: &mut R, raster: &RasterVoronoi, sites: &[Point]) -> Result<(), VoronoiError> {
+    let colors = COLORS
+        .iter()
+        .map(|&color| parse_color(color).ok_or_else(|| VoronoiError::Render(format!("Unknown color {}", color))))
+        .collect::<Result<Vec<_>, _>>()?;
+    let image = Image {
+        width: raster.width,
+        height: raster.height,
+        pixels: raster.to_rgba(&colors),
+    };
+    renderer.image(Point::new(0.0, 0.0), &image)?;
+
+    render::sites(renderer, sites, 2.0, &Style::stroke("#333333", 4.0))
+}
 
-        }
+/// Looks up a metric by the name used in the page: "euclidean", "manhattan", "chebyshev", or
+/// "minkowski-p" for the Lp metric with the given p
+#[cfg(feature = "wasm")]
+fn metric_by_name(name: &str) -> Result<Box<dyn Metric>, JsValue> {
+    match name {
+        "euclidean" => Ok(Box::new(Euclidean)),
+        "manhattan" => Ok(Box::new(Manhattan)),
+        "chebyshev" => Ok(Box::new(Chebyshev)),
+        _ => name
+            .strip_prefix("minkowski-")
+            .and_then(|p| p.parse().ok())
+            .map(|p| Box::new(Minkowski::new(p)) as Box<dyn Metric>)
+            .ok_or_else(|| js_sys::Error::new(&format!("Unknown metric {}", name)).into()),
     }
-
-    context.set_stroke_style(&JsValue::from_str("#333333"));
-    // Render each point as a little circle, and draw guidance lines to each
-    for (i, point) in sites.iter().enumerate() {
-        let (x, y): (f64, f64) = (point.x.into(), point.y.into());
-
-
-        context.begin_path();
-        context
-            .arc(x, y, 2.0, 0.0, std::f64::consts::PI * 2.0)
-            .unwrap();
-        context.stroke();
-    }   
-    
-
-
-    Ok(())
 }
-
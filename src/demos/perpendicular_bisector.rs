@@ -1,38 +1,54 @@
-///
-/// Demo 3: Perpendicular Bisectors
-/// 
-/// For each pair of randomly generated points, computes and draws the perpendicular bisector. This
-/// is an important first step in finding the Dulaney triangulation.
-/// 
-/// 
-
+//!
+//! Demo 3: Perpendicular Bisectors
+//! 
+//! For each pair of randomly generated points, computes and draws the perpendicular bisector. This
+//! is an important first step in finding the Dulaney triangulation.
+//! 
+//! 
+
+use crate::error::VoronoiError;
+use crate::point::Point;
+use crate::render::{Renderer, Style};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "wasm")]
 use super::demo_setup;
 
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn demo3(
     canvas: web_sys::HtmlCanvasElement,
     num_points: u32,
+    seed: u64,
 ) -> Result<(), JsValue> {
-    
-    let (context, sites) = demo_setup(&canvas, num_points);
 
+    let (mut renderer, sites) = demo_setup(&canvas, num_points, seed)?;
+    draw_bisectors(&mut renderer, &sites, canvas.width() as f64)?;
+
+    Ok(())
+}
+
+/// Draws the perpendicular bisector of every pair of sites, faintly, across a canvas `width` wide
+pub fn draw_bisectors<R: Renderer + ?Sized>(renderer: &mut R, sites: &[Point], width: f64) -> Result<(), VoronoiError> {
     // Get the min and max x value for the canvas, which we use for drawing
-    let (x_min, x_max) = (0.0, canvas.width() as f64 - 1.0);
+    let (x_min, x_max) = (0.0, width - 1.0);
 
-    context.set_stroke_style(&JsValue::from_str("rgba(250, 177, 160, 0.1)"));
-    context.set_line_width(3.0);
+    renderer.style(&Style::stroke("rgba(250, 177, 160, 0.1)", 3.0))?;
 
     // Compute the bisectors for each pair of points.
     for a in sites.iter() {
         for b in sites.iter() {
+            // A site has no bisector with itself
+            if a == b {
+                continue;
+            }
 
             // Midpoint
-            let (x_m, y_m) = ((a.x + b.x) as f64 / 2.0, (a.y + b.y) as f64 / 2.0);
+            let (x_m, y_m) = ((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
 
             // Slope & Slope Inverse
-            let m = (b.y - a.y) as f64 / (b.x - a.x) as f64;
+            let m = (b.y - a.y) / (b.x - a.x);
             let m_inv = -1.0 / m;
 
             // The function of the perpendicular bisector is now known:
@@ -41,18 +57,9 @@ pub fn demo3(
             let (x_b, y_b) = (x_max, m_inv * (x_max - x_m) + y_m);
 
             // Draw the bisector
-            context.begin_path();
-            context.move_to(x_a, y_a);
-            context.line_to(x_b, y_b);
-            context.stroke();
-
-
-
+            renderer.polyline(&[Point::new(x_a, y_a), Point::new(x_b, y_b)])?;
         }
     };
 
-
-
     Ok(())
 }
-
@@ -1,6 +1,6 @@
+//! Defines a bisector type, which is a line segment defined as the portion of a perpendicular
+//! bisector of 2 points (voronoi sites)
 use crate::point::Point;
-/// Defines a bisector type, which is a line segment defined as the portion of a perpendicular
-/// bisector of 2 points (voronoi sites)
 use std::{fmt::Display, num::FpCategory};
 
 #[derive(Clone, Copy)]
@@ -39,8 +39,8 @@ impl Bisector {
         let max_width = f64::INFINITY;
         let max_height = f64::INFINITY;
 
-        let (x_m, y_m) = ((a.x + b.x) as f64 / 2.0, (a.y + b.y) as f64 / 2.0);
-        let midpoint = Point::new(x_m as i32, y_m as i32);
+        let (x_m, y_m) = ((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+        let midpoint = Point::new(x_m, y_m);
 
         // Special case: if the points have the same x value, then the bisector will be a horizontal
         // line with slope zero, and the y bounds will be the midpoint y value.
@@ -49,7 +49,7 @@ impl Bisector {
             let slope = 0.0;
 
             let x_min = min_width;
-            let x_max = max_width as f64;
+            let x_max = max_width;
 
             let y_min = y_m;
             let y_max = y_m;
@@ -67,7 +67,7 @@ impl Bisector {
             }
         } else {
             // Calculate the slope of the line segment connecting the two points
-            let segment_slope = (b.y - a.y) as f64 / (b.x - a.x) as f64;
+            let segment_slope = (b.y - a.y) / (b.x - a.x);
 
             // Handle vertical lines, which have infinite slope
             if segment_slope.classify() == FpCategory::Zero {
@@ -99,11 +99,11 @@ impl Bisector {
                 // Compute the two endpoints of the bisector. Because of negative values, we need to compute
                 // these, and then order them separately.
                 let x_a = x_m + segment_slope * (y_m - min_height);
-                let x_b = x_m + segment_slope * (y_m - max_height as f64);
+                let x_b = x_m + segment_slope * (y_m - max_height);
 
                 // Sort the endpoints into min and max
                 let x_min = f64::max(min_width, f64::min(x_a, x_b));
-                let x_max = f64::min(max_width.into(), f64::max(x_a, x_b));
+                let x_max = f64::min(max_width, f64::max(x_a, x_b));
 
                 let y_min = f64::NEG_INFINITY;
                 let y_max = f64::INFINITY;
@@ -144,9 +144,9 @@ impl Bisector {
         if self.is_vertical() {
             None
         } else if self.is_horizontal() {
-            Some(self.point.y as f64)
+            Some(self.point.y)
         } else {
-            Some(self.slope * (x - self.point.x as f64) + self.point.y as f64)
+            Some(self.slope * (x - self.point.x) + self.point.y)
         }
     }
 
@@ -156,9 +156,9 @@ impl Bisector {
         if self.is_horizontal() {
             None
         } else if self.is_vertical() {
-            Some(self.point.x as f64)
+            Some(self.point.x)
         } else {
-            Some(self.point.x as f64 + (y - self.point.y as f64) / self.slope)
+            Some(self.point.x + (y - self.point.y) / self.slope)
         }
     }
 
@@ -186,18 +186,18 @@ impl Bisector {
 
             // Cases where just one is vertical
             (true, false) => {
-                if (other.x_min..other.x_max).contains(&(self.point.x as f64)) {
+                if (other.x_min..other.x_max).contains(&self.point.x) {
                     other
-                        .compute(self.point.x as f64)
-                        .map(|y| Point::new(self.point.x as i32, y as i32))
+                        .compute(self.point.x)
+                        .map(|y| Point::new(self.point.x, y))
                 } else {
                     None
                 }
             }
             (false, true) => {
-                if (self.x_min..self.x_max).contains(&(other.point.x as f64)) {
-                    self.compute(other.point.x as f64)
-                        .map(|y| Point::new(other.point.x as i32, y as i32))
+                if (self.x_min..self.x_max).contains(&other.point.x) {
+                    self.compute(other.point.x)
+                        .map(|y| Point::new(other.point.x, y))
                 } else {
                     None
                 }
@@ -225,7 +225,7 @@ impl Bisector {
 
                 // Only return the point if it is in both x ranges
                 if (self.x_min..self.x_max).contains(&x) && (other.x_min..other.x_max).contains(&x) {
-                    Some(Point::new(x as i32, y as i32))
+                    Some(Point::new(x, y))
                 } else {
                     None
                 }
@@ -273,7 +273,7 @@ impl Bisector {
             None
         } else {
             // Make 2 copies
-            let (mut left, mut right) = (self.clone(), self.clone());
+            let (mut left, mut right) = (*self, *self);
 
             // Update the boundaries
             left.set_max_x(x);
@@ -291,7 +291,7 @@ impl Bisector {
             None
         } else {
             // Make 2 copies
-            let (mut bottom, mut top) = (self.clone(), self.clone());
+            let (mut bottom, mut top) = (*self, *self);
 
             // Update the boundaries
             bottom.set_max_y(y);
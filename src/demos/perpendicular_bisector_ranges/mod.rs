@@ -1,38 +1,64 @@
+//!
+//! Demo 4: Perpendicular Bisector with x ranges
+//! 
 
-///
-/// Demo 4: Perpendicular Bisector with x ranges
-/// 
-
-use wasm_bindgen::prelude::*;
+use crate::error::VoronoiError;
 use crate::point::Point;
+use crate::render::{Renderer, Style};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
 
 use self::bisector::Bisector;
+#[cfg(feature = "wasm")]
 use super::demo_setup;
+#[cfg(feature = "wasm")]
 use crate::console_log;
 
-mod bisector;
+pub mod bisector;
 
+/// Two bisectors that cross, by their indices, and where they cross
+pub type Intersection = (usize, usize, Point);
 
+
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn demo4(
     canvas: web_sys::HtmlCanvasElement,
     num_points: u32,
+    seed: u64,
 ) -> Result<(), JsValue> {
     
-    let (context, mut sites) = demo_setup(&canvas, num_points);
+    let (mut renderer, sites) = demo_setup(&canvas, num_points, seed)?;
+    let (bisectors, intersections) = draw_bisector_intersections(&mut renderer, &sites, canvas.width() as f64, canvas.height() as f64)?;
 
+    console_log!("{} bisectors total", bisectors.len());
 
+    for (i, j, point) in intersections {
+        console_log!("{} intersects with {} at {}", bisectors[i], bisectors[j], point);
+    }
 
 
+    Ok(())
+}
+
+/// Draws the perpendicular bisector of every pair of sites, faintly, across a `width` by `height`
+/// canvas, then a small circle wherever two of them cross. Returns the bisectors, and the indices
+/// of each pair of bisectors that cross along with where.
+pub fn draw_bisector_intersections<R: Renderer + ?Sized>(
+    renderer: &mut R,
+    sites: &[Point],
+    width: f64,
+    height: f64,
+) -> Result<(Vec<Bisector>, Vec<Intersection>), VoronoiError> {
+
     // Get the min and max x value for the canvas, which we use for drawing
-    let (x_min, x_max) = (0.0, canvas.width() as f64 - 1.0);
-    let (y_min, y_max) = (0.0, canvas.height() as f64 - 1.0);
+    let (x_min, x_max) = (0.0, width - 1.0);
+    let (y_min, y_max) = (0.0, height - 1.0);
 
-    context.set_stroke_style(&JsValue::from_str("rgba(250, 177, 160, 0.1)"));
-    context.set_line_width(3.0);
+    renderer.style(&Style::stroke("rgba(250, 177, 160, 0.1)", 3.0))?;
 
     let n = sites.len();
-    let mut bisectors: Vec<Bisector> = Vec::with_capacity(n * (n-1) / 2);
+    let mut bisectors: Vec<Bisector> = Vec::with_capacity(n * n.saturating_sub(1) / 2);
 
     // Compute the bisector for each pair of sites
     for i in 0..n {
@@ -42,28 +68,17 @@ pub fn demo4(
 
             let bisector = Bisector::new(a, b);
 
-            let (mut x_a, mut y_a) = (0.0, 0.0);
-            let (mut x_b, mut y_b) = (0.0, 0.0);
-
-            if bisector.is_vertical() {
-                y_a = y_min;
-                y_b = y_max;
+            let (x_a, y_a, x_b, y_b) = if bisector.is_vertical() {
+                let (y_a, y_b) = (y_min, y_max);
 
-                x_a = bisector.compute_inv(y_a).unwrap();
-                x_b = bisector.compute_inv(y_b).unwrap();
+                (bisector.compute_inv(y_a).unwrap(), y_a, bisector.compute_inv(y_b).unwrap(), y_b)
             } else {
-                x_a = x_min;
-                x_b = x_max;
+                let (x_a, x_b) = (x_min, x_max);
 
-                y_a = bisector.compute(x_a).unwrap();
-                y_b = bisector.compute(x_b).unwrap();
-                
-            }
+                (x_a, bisector.compute(x_a).unwrap(), x_b, bisector.compute(x_b).unwrap())
+            };
 
-            context.begin_path();
-            context.move_to(x_a, y_a);
-            context.line_to(x_b, y_b);
-            context.stroke();
+            renderer.polyline(&[Point::new(x_a, y_a), Point::new(x_b, y_b)])?;
             
             bisectors.push(bisector);
 
@@ -71,29 +86,20 @@ pub fn demo4(
     };
 
     let n = bisectors.len();
-
-    console_log!("{} bisectors total", n);
+    let mut intersections = Vec::new();
 
     for i in 0..n {
         for j in (i+1)..n {
-            let a = bisectors[i];
-            let b = bisectors[j];
-
-            if let Some(point) = a.intersection(&b) {
-                console_log!("{} intersects with {} at {}", a, b, point);
-
-                context.begin_path();
-                context
-                    .arc(point.x.into(), point.y.into(), 3.0, 0.0, std::f64::consts::PI * 2.0)
-                    .unwrap();
-                context.stroke();
-
+            if let Some(point) = bisectors[i].intersection(&bisectors[j]) {
+                intersections.push((i, j, point));
             }
-
         }
     };
 
+    // Each intersection is marked with a little circle, in the same faint style
+    for &(_, _, point) in intersections.iter() {
+        renderer.circle(point, 3.0)?;
+    }
 
-    Ok(())
+    Ok((bisectors, intersections))
 }
-
@@ -0,0 +1,256 @@
+//!
+//! The exact Euclidean distance transform, with the algorithm by Felzenszwalb and Huttenlocher:
+//! the squared distance from every pixel of a raster to its nearest site, and which site that is
+//! (the feature transform). It takes linear time in the number of pixels, however many sites
+//! there are.
+//!
+//! Squared distances split into a vertical part and a horizontal part, so the transform is done
+//! in two passes. The first finds the nearest site in each pixel's own column. The second treats
+//! each column's answer as a parabola along the row, and finds the lowest parabola at every pixel
+//! by building their lower envelope.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::point::Point;
+use crate::raster::{RasterVoronoi, NO_SITE};
+
+/// Finds the squared Euclidean distance from every pixel of a `width` by `height` raster to its
+/// nearest site, and the index of that site. Each site is seeded at the pixel it is closest to
+/// (or the nearest pixel on the edge, for a site outside the raster), and distances are measured
+/// to that pixel, so they are exact for sites with whole-number coordinates.
+///
+/// The labels of the result are the feature transform, and its distances are squared. When two
+/// sites start at the same pixel, the one nearer to it wins (or the first, if they are equally
+/// near), and the other can't reach any pixel at all. Other ties go to either site.
+pub fn distance_transform(width: u32, height: u32, sites: &[Point]) -> RasterVoronoi {
+    let (w, h) = (width as usize, height as usize);
+    let mut labels = vec![NO_SITE; w * h];
+    let mut distances = vec![f64::INFINITY; w * h];
+
+    if w == 0 || h == 0 {
+        return RasterVoronoi {
+            width,
+            height,
+            labels,
+            distances: Some(distances),
+        };
+    }
+
+    // Seed each site at its own pixel
+    let mut seeds = vec![NO_SITE; w * h];
+    for (i, &site) in sites.iter().enumerate() {
+        if !site.x.is_finite() || !site.y.is_finite() {
+            continue;
+        }
+
+        let x = site.x.round().max(0.0).min((w - 1) as f64) as usize;
+        let y = site.y.round().max(0.0).min((h - 1) as f64) as usize;
+        let to_pixel = |site: Point| (site.x - x as f64).powi(2) + (site.y - y as f64).powi(2);
+
+        let seed = &mut seeds[y * w + x];
+        if *seed == NO_SITE || to_pixel(site) < to_pixel(sites[*seed as usize]) {
+            *seed = i as u32;
+        }
+    }
+
+    // Down each column, the nearest seed in that column, by scanning down and then back up
+    let mut column_sites = vec![NO_SITE; w * h];
+    let mut column_distances = vec![f64::INFINITY; w * h];
+    for x in 0..w {
+        let mut nearest: Option<usize> = None;
+        for y in 0..h {
+            if seeds[y * w + x] != NO_SITE {
+                nearest = Some(y);
+            }
+            if let Some(row) = nearest {
+                column_sites[y * w + x] = seeds[row * w + x];
+                column_distances[y * w + x] = ((y - row) * (y - row)) as f64;
+            }
+        }
+
+        let mut nearest: Option<usize> = None;
+        for y in (0..h).rev() {
+            if seeds[y * w + x] != NO_SITE {
+                nearest = Some(y);
+            }
+            if let Some(row) = nearest {
+                let d = ((row - y) * (row - y)) as f64;
+                if d < column_distances[y * w + x] {
+                    column_sites[y * w + x] = seeds[row * w + x];
+                    column_distances[y * w + x] = d;
+                }
+            }
+        }
+    }
+
+    // Along each row, the lowest of the parabolas (x - x')² + column_distances[x'], for every
+    // column x' that has a seed at all
+    let mut envelope = Envelope::with_capacity(w);
+    for y in 0..h {
+        let row = y * w;
+        envelope.build((0..w).filter(|&x| column_sites[row + x] != NO_SITE).map(|x| (x, column_distances[row + x])));
+        if envelope.parabolas.is_empty() {
+            continue;
+        }
+
+        for (x, column) in envelope.lowest(w) {
+            let dx = x as f64 - column as f64;
+            labels[row + x] = column_sites[row + column];
+            distances[row + x] = dx * dx + column_distances[row + column];
+        }
+    }
+
+    // Pixels no site reached (only possible with no sites) have no distance either
+    for (distance, &label) in distances.iter_mut().zip(labels.iter()) {
+        if label == NO_SITE {
+            *distance = f64::NAN;
+        }
+    }
+
+    RasterVoronoi {
+        width,
+        height,
+        labels,
+        distances: Some(distances),
+    }
+}
+
+/// The lower envelope of parabolas (x - vertex)² + height, with their vertices in increasing order
+struct Envelope {
+    /// The vertex and height of each parabola on the envelope, from left to right
+    parabolas: Vec<(usize, f64)>,
+
+    /// Where each parabola on the envelope starts being the lowest. The first starts at -∞.
+    starts: Vec<f64>,
+}
+
+impl Envelope {
+    fn with_capacity(capacity: usize) -> Envelope {
+        Envelope {
+            parabolas: Vec::with_capacity(capacity),
+            starts: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Replaces the envelope with the envelope of the given parabolas, which have to be in order
+    /// of their vertices
+    fn build<I: Iterator<Item = (usize, f64)>>(&mut self, parabolas: I) {
+        self.parabolas.clear();
+        self.starts.clear();
+
+        for (vertex, height) in parabolas {
+            // Drop parabolas from the end of the envelope while the new one is lower than them
+            // everywhere they were the lowest
+            let mut start = f64::NEG_INFINITY;
+            while let Some(&(last, last_height)) = self.parabolas.last() {
+                start = crossing((last, last_height), (vertex, height));
+                if start > *self.starts.last().unwrap() {
+                    break;
+                }
+
+                self.parabolas.pop();
+                self.starts.pop();
+                start = f64::NEG_INFINITY;
+            }
+
+            self.parabolas.push((vertex, height));
+            self.starts.push(start);
+        }
+    }
+
+    /// For every x from 0 to `width` - 1, the vertex of the lowest parabola there
+    fn lowest(&self, width: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut k = 0;
+        (0..width).map(move |x| {
+            while k + 1 < self.parabolas.len() && self.starts[k + 1] <= x as f64 {
+                k += 1;
+            }
+
+            (x, self.parabolas[k].0)
+        })
+    }
+}
+
+/// Where two parabolas (x - vertex)² + height cross, with the first one's vertex to the left
+fn crossing((p, p_height): (usize, f64), (q, q_height): (usize, f64)) -> f64 {
+    let (p, q) = (p as f64, q as f64);
+
+    ((q_height + q * q) - (p_height + p * p)) / (2.0 * (q - p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{seeded_rng, uniform};
+    use crate::jfa::{jump_flooding, Variant};
+    use crate::metric::Euclidean;
+
+    const WIDTH: u32 = 40;
+    const HEIGHT: u32 = 30;
+
+    /// The pixel each site is seeded at
+    fn seed_pixel(site: Point) -> Point {
+        Point::new(
+            site.x.round().max(0.0).min((WIDTH - 1) as f64),
+            site.y.round().max(0.0).min((HEIGHT - 1) as f64),
+        )
+    }
+
+    fn squared(a: Point, b: Point) -> f64 {
+        (a.x - b.x).powi(2) + (a.y - b.y).powi(2)
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        for seed in 0..20 {
+            // Some sites land outside the raster, and some share a pixel
+            let sites = uniform(&mut seeded_rng(seed), 2 + seed as usize * 3, -5.0..45.0, -5.0..35.0);
+            let transform = distance_transform(WIDTH, HEIGHT, &sites);
+            let distances = transform.distances.as_ref().unwrap();
+
+            for y in 0..HEIGHT {
+                for x in 0..WIDTH {
+                    let p = Point::new(x.into(), y.into());
+                    let nearest = sites.iter().map(|&site| squared(seed_pixel(site), p)).fold(f64::INFINITY, f64::min);
+                    let i = (y * WIDTH + x) as usize;
+                    assert_eq!(distances[i], nearest, "seed {}, pixel ({}, {})", seed, x, y);
+
+                    // Any of the nearest sites will do
+                    let label = transform.label_at(x, y).unwrap();
+                    assert_eq!(squared(seed_pixel(sites[label]), p), nearest, "seed {}, pixel ({}, {})", seed, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sites_sharing_a_pixel() {
+        for &(first, second, winner) in [
+            (Point::new(5.2, 5.0), Point::new(4.9, 5.1), 1),
+            (Point::new(5.25, 5.0), Point::new(4.75, 5.0), 0),
+        ]
+        .iter()
+        {
+            let sites = [first, second, Point::new(30.0, 20.0)];
+
+            let transform = distance_transform(WIDTH, HEIGHT, &sites);
+            let flooded = jump_flooding(WIDTH, HEIGHT, &sites, &Euclidean, Variant::Jfa);
+            assert_eq!(transform.label_at(5, 5), Some(winner));
+            assert_eq!(flooded.label_at(5, 5), Some(winner));
+
+            let loser = 1 - winner as u32;
+            assert!(!transform.labels.contains(&loser));
+            assert!(!flooded.labels.contains(&loser));
+        }
+    }
+
+    #[test]
+    fn no_sites() {
+        let transform = distance_transform(4, 3, &[]);
+        assert!(transform.labels.iter().all(|&label| label == NO_SITE));
+        assert_eq!(transform.distance_at(1, 1), None);
+
+        assert!(distance_transform(0, 3, &[Point::new(0.0, 0.0)]).labels.is_empty());
+    }
+}
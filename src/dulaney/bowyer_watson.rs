@@ -0,0 +1,288 @@
+//!
+//! The Bowyer–Watson algorithm, inserting one point at a time into a triangulation that stores
+//! the neighbors of each triangle.
+//!
+//! Rather than enclosing every point in a super triangle, the triangulation is closed off with
+//! ghost triangles: one for each edge of the convex hull, joining it to a single vertex at
+//! infinity. A point outside the hull is inside the "circumcircle" of every ghost triangle whose
+//! hull edge it can see, so points outside the hull are handled exactly like points inside it, and
+//! the hull comes out exactly right.
+//!
+//! Each insertion walks from the previous point to the triangle containing the new one, then
+//! grows the cavity of triangles whose circumcircle contains the point out from there, through
+//! their neighbors. Along with the insertion order, this makes the expected cost of an insertion
+//! constant.
+//!
+//! With weights, the same insertion builds the regular triangulation, replacing circumcircles with
+//! the power test. A weighted point can be hidden: if it isn't in conflict with the triangle it
+//! lands in, it has no cell in the power diagram, and it is left out. Points already in the
+//! triangulation can also become hidden, when every triangle around them is in the cavity.
+//!
+
+use crate::point::{Point, WeightedPoint};
+use crate::predicates::{incircle, orient2d, power_test};
+
+/// The vertex at infinity, shared by every ghost triangle
+const GHOST: usize = usize::MAX;
+
+/// Marks a missing neighbor, only used while the triangles are being created
+const NONE: usize = usize::MAX;
+
+pub struct BowyerWatson<'a> {
+    points: &'a [Point],
+
+    /// The weight of each point, for a regular triangulation
+    weights: Option<&'a [f64]>,
+
+    /// The vertices of each triangle, counter-clockwise
+    triangles: Vec<[usize; 3]>,
+
+    /// `neighbors[t][i]` is the triangle across the edge from vertex i to vertex i + 1 of t
+    neighbors: Vec<[usize; 3]>,
+
+    /// The insertion each triangle was last found to be in the cavity of
+    visited: Vec<usize>,
+
+    /// A triangle near the last point inserted, where the next walk starts
+    last: usize,
+
+    insertions: usize,
+
+    /// Slots of triangles that were removed without being replaced, which only happens when a
+    /// vertex becomes hidden. Their vertices are all GHOST.
+    free: Vec<usize>,
+
+    // Buffers for the cavity, reused between insertions
+    stack: Vec<usize>,
+    cavity: Vec<usize>,
+    boundary: Vec<(usize, usize, usize)>,
+}
+
+impl<'a> BowyerWatson<'a> {
+    /// Starts a triangulation from the triangle a, b, c, which must not be collinear
+    pub fn new(points: &'a [Point], a: usize, b: usize, c: usize) -> BowyerWatson<'a> {
+        let (b, c) = if orient2d(points[a], points[b], points[c]) < 0.0 {
+            (c, b)
+        } else {
+            (b, c)
+        };
+
+        // The triangle, and a ghost triangle across each of its edges
+        let triangles = vec![[a, b, c], [b, a, GHOST], [c, b, GHOST], [a, c, GHOST]];
+        let neighbors = vec![[1, 2, 3], [0, 3, 2], [0, 1, 3], [0, 2, 1]];
+
+        BowyerWatson {
+            points,
+            weights: None,
+            triangles,
+            neighbors,
+            visited: vec![0; 4],
+            last: 0,
+            insertions: 0,
+            free: Vec::new(),
+            stack: Vec::new(),
+            cavity: Vec::new(),
+            boundary: Vec::new(),
+        }
+    }
+
+    /// Starts a regular triangulation, where `weights[i]` is the weight of point i
+    pub fn weighted(points: &'a [Point], weights: &'a [f64], a: usize, b: usize, c: usize) -> BowyerWatson<'a> {
+        BowyerWatson {
+            weights: Some(weights),
+            ..BowyerWatson::new(points, a, b, c)
+        }
+    }
+
+    /// Inserts a point, returning false if it is hidden. Unweighted points are never hidden.
+    pub fn insert(&mut self, p: usize) -> bool {
+        self.insertions += 1;
+
+        let start = self.locate(self.points[p]);
+        if !self.in_circumcircle(start, p) {
+            return false;
+        }
+
+        self.dig_cavity(start, p);
+        self.fill_cavity(p);
+
+        true
+    }
+
+    /// The real (non-ghost) triangles, as the indices of their vertices
+    pub fn into_triangles(self) -> Vec<[usize; 3]> {
+        self.triangles
+            .into_iter()
+            .filter(|triangle| !triangle.contains(&GHOST))
+            .collect()
+    }
+
+    /// Walks from the last triangle towards p, crossing any edge that p is on the far side of.
+    /// Returns the triangle containing p or, if p is outside the hull, a ghost triangle whose hull
+    /// edge p can see. In a Dulaney or regular triangulation, this walk never gets stuck in a loop.
+    fn locate(&self, p: Point) -> usize {
+        // Ghost triangles can't be walked through, so start from the real triangle across the
+        // hull edge instead. The hull edge is the one opposite the ghost vertex.
+        let mut current = self.last;
+        if let Some(g) = self.triangles[current].iter().position(|&v| v == GHOST) {
+            current = self.neighbors[current][(g + 1) % 3];
+        }
+
+        // Start testing from a different edge each time, so the walk can't keep making the same
+        // unlucky choice
+        let offset = self.insertions;
+
+        'walk: loop {
+            let vertices = self.triangles[current];
+
+            for k in 0..3 {
+                let i = (k + offset) % 3;
+                let (u, w) = (vertices[i], vertices[(i + 1) % 3]);
+
+                if orient2d(self.points[u], self.points[w], p) < 0.0 {
+                    current = self.neighbors[current][i];
+
+                    if self.triangles[current].contains(&GHOST) {
+                        return current;
+                    }
+                    continue 'walk;
+                }
+            }
+
+            return current;
+        }
+    }
+
+    /// Finds every triangle whose circumcircle contains p, starting from the one `locate` found,
+    /// and records the edges around them
+    fn dig_cavity(&mut self, start: usize, p: usize) {
+        self.cavity.clear();
+        self.boundary.clear();
+        self.stack.clear();
+
+        self.visited[start] = self.insertions;
+        self.stack.push(start);
+
+        while let Some(t) = self.stack.pop() {
+            self.cavity.push(t);
+
+            for i in 0..3 {
+                let neighbor = self.neighbors[t][i];
+                if self.visited[neighbor] == self.insertions {
+                    continue;
+                }
+
+                if self.in_circumcircle(neighbor, p) {
+                    self.visited[neighbor] = self.insertions;
+                    self.stack.push(neighbor);
+                } else {
+                    let (u, w) = (self.triangles[t][i], self.triangles[t][(i + 1) % 3]);
+                    self.boundary.push((u, w, neighbor));
+                }
+            }
+        }
+    }
+
+    /// Replaces the cavity with a fan of triangles joining p to each edge on its boundary
+    fn fill_cavity(&mut self, p: usize) {
+        let first = self.cavity.len();
+
+        // Reuse the slots of the removed triangles, then add more on the end. The fan has two
+        // more triangles than the cavity did, unless vertices inside the cavity became hidden.
+        let mut fan = Vec::with_capacity(self.boundary.len());
+        for (k, &(u, w, outside)) in self.boundary.iter().enumerate() {
+            let t = if k < first {
+                self.cavity[k]
+            } else if let Some(t) = self.free.pop() {
+                t
+            } else {
+                self.triangles.push([0; 3]);
+                self.neighbors.push([NONE; 3]);
+                self.visited.push(0);
+                self.triangles.len() - 1
+            };
+
+            self.triangles[t] = [u, w, p];
+            self.neighbors[t] = [outside, NONE, NONE];
+
+            // Point the triangle outside the cavity back at its new neighbor
+            let [a, b, c] = self.triangles[outside];
+            let j = if a == w && b == u {
+                0
+            } else if b == w && c == u {
+                1
+            } else {
+                2
+            };
+            self.neighbors[outside][j] = t;
+
+            fan.push(t);
+        }
+
+        for k in self.boundary.len()..first {
+            let t = self.cavity[k];
+            self.triangles[t] = [GHOST; 3];
+            self.free.push(t);
+        }
+
+        // Triangles in the fan are neighbors when the boundary edge of one ends where the other's
+        // begins. Cavities are small, so searching is faster than a map.
+        for &t in fan.iter() {
+            let w = self.triangles[t][1];
+
+            if let Some(&next) = fan.iter().find(|&&other| self.triangles[other][0] == w) {
+                self.neighbors[t][1] = next;
+                self.neighbors[next][2] = t;
+            }
+        }
+
+        self.last = fan
+            .iter()
+            .copied()
+            .find(|&t| !self.triangles[t].contains(&GHOST))
+            .unwrap_or(fan[0]);
+    }
+
+    /// Tests if point p is strictly inside the circumcircle of a triangle (or, with weights, in
+    /// conflict with it). The "circumcircle" of a ghost triangle is the open half-plane outside its
+    /// hull edge, plus the points on the edge's line that conflict with the triangle across it.
+    fn in_circumcircle(&self, t: usize, p: usize) -> bool {
+        let [a, b, c] = self.triangles[t];
+
+        // The hull edge, and its index in the triangle
+        let (u, w, edge) = if c == GHOST {
+            (a, b, 0)
+        } else if a == GHOST {
+            (b, c, 1)
+        } else if b == GHOST {
+            (c, a, 2)
+        } else {
+            return match self.weights {
+                None => incircle(self.points[a], self.points[b], self.points[c], self.points[p]) > 0.0,
+                Some(weights) => {
+                    let weighted = |i: usize| WeightedPoint { point: self.points[i], weight: weights[i] };
+                    power_test(weighted(a), weighted(b), weighted(c), weighted(p)) > 0.0
+                }
+            };
+        };
+
+        let (point, u_point, w_point) = (self.points[p], self.points[u], self.points[w]);
+        let orientation = orient2d(u_point, w_point, point);
+        if orientation != 0.0 {
+            return orientation > 0.0;
+        }
+
+        // On the line through the hull edge. The real triangle across the edge meets the line in
+        // the same place, so the two triangles conflict with exactly the same points on it.
+        // Without weights, those are the points on the edge itself.
+        if self.weights.is_some() {
+            return self.in_circumcircle(self.neighbors[t][edge], p);
+        }
+
+        if u_point.x != w_point.x {
+            (u_point.x < point.x && point.x < w_point.x) || (w_point.x < point.x && point.x < u_point.x)
+        } else {
+            (u_point.y < point.y && point.y < w_point.y) || (w_point.y < point.y && point.y < u_point.y)
+        }
+    }
+}
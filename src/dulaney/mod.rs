@@ -1,89 +1,231 @@
-///
-/// An implementation of the Dulaney Triangulation using the Bowyer–Watson algorithm. The Dulaney
-/// Triangulation is a way to represent a number of points (the graph) as a series of connected triangles. 
-/// Conveniently, this triangulation is a dual graph of the Voronoi diagram for the same set of
-/// points. 
-/// 
-/// Author: Brendan McGuire
-/// Date: 18 July 2021
-/// 
-/// https://bren.app/voronoi/
-/// 
-
+//!
+//! An implementation of the Dulaney Triangulation using the Bowyer–Watson algorithm. The Dulaney
+//! Triangulation is a way to represent a number of points (the graph) as a series of connected triangles. 
+//! Conveniently, this triangulation is a dual graph of the Voronoi diagram for the same set of
+//! points. 
+//! 
+//! Author: Brendan McGuire
+//! Date: 18 July 2021
+//! 
+//! https://bren.app/voronoi/
+//! 
+
+mod bowyer_watson;
+mod order;
 mod triangle;
 
-use triangle::Triangle;
+pub use triangle::Triangle;
 use super::Point;
-use web_sys::console;
+use bowyer_watson::BowyerWatson;
+use crate::error::{validate_sites, validate_weighted_sites, VoronoiError};
+use crate::mesh::{FaceId, Mesh};
+use crate::point::WeightedPoint;
+use crate::predicates::orient2d;
+
+/// A triangulation of a set of points. The mesh holds the adjacency between the triangles: vertex
+/// i of the mesh is point i, and face i of the mesh is triangles[i].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangulation {
+    pub mesh: Mesh,
+    pub triangles: Vec<Triangle>,
+}
+
+impl Triangulation {
+    /// Builds a triangulation from triangles given as the indices of their three points, in any
+    /// order. Fails if the points are not valid, if there are no triangles, if a triangle is flat
+    /// or overlaps another, or if two triangles only meet at a corner (see Mesh::from_faces).
+    pub fn from_triangles(points: &[Point], triangles: &[[usize; 3]]) -> Result<Triangulation, VoronoiError> {
+        validate_sites(points)?;
+        Self::from_valid_triangles(points, triangles)
+    }
 
-pub fn dulaney_triangulation(points: &[Point], width: i32, height: i32) -> Vec<Triangle> {
+    /// Like from_triangles, for points that have already been validated
+    pub(crate) fn from_valid_triangles(points: &[Point], triangles: &[[usize; 3]]) -> Result<Triangulation, VoronoiError> {
+        if triangles.is_empty() {
+            return Err(VoronoiError::DegenerateInput);
+        }
 
-    let mut triangulation = Vec::new();
+        // Faces of the mesh have to run counter-clockwise
+        let faces: Vec<[usize; 3]> = triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                if orient2d(points[a], points[b], points[c]) < 0.0 {
+                    [a, c, b]
+                } else {
+                    [a, b, c]
+                }
+            })
+            .collect();
 
-    // First, create the super triangle, which contains all of the points. The easiest way to do
-    // this is to have the super triangle contain (circumscribe) the entire canvas.
-    // See https://www.desmos.com/calculator/ckld3teujk for how the dimensions of the super triangle
-    // can be calculated.
+        let mesh = Mesh::from_faces(points, &faces)?;
+        let triangles = faces
+            .iter()
+            .map(|&[a, b, c]| Triangle::new(points[a], points[b], points[c]))
+            .collect::<Result<Vec<_>, _>>()?;
 
-    let super_triangle = Triangle::new(
-        Point::new(width / 2, 2 * height),
-        Point::new(-width, 0),
-        Point::new(2 * width, 0)
-    );
-    triangulation.push(super_triangle);
+        Ok(Triangulation { mesh, triangles })
+    }
 
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.mesh.vertices().map(move |vertex| self.mesh.point(vertex))
+    }
 
-    for point in points {
+    pub fn triangle(&self, face: FaceId) -> &Triangle {
+        &self.triangles[face.0]
+    }
+}
 
-        // Find the bad triangles, whose circumcircle contain this point
-        let mut bad_triangles = Vec::new();
-        for triangle in triangulation.iter() {
+/// Triangulates the points with the Bowyer–Watson algorithm, in O(n log n) expected time.
+///
+/// Fails if there are no points, a coordinate is out of range, a point is repeated, or the points
+/// are all collinear.
+pub fn dulaney_triangulation(points: &[Point]) -> Result<Triangulation, VoronoiError> {
 
+    validate_sites(points)?;
 
-            if triangle.circumcircle_contains(point) {
-                bad_triangles.push(triangle.clone());
-            }
+    let order = order::brio(points);
+    let c = first_triangle(points, &order)?;
+
+    let mut triangulation = BowyerWatson::new(points, order[0], order[1], order[c]);
+    for (i, &point) in order.iter().enumerate().skip(2) {
+        if i != c {
+            triangulation.insert(point);
         }
+    }
 
-        // Find the boundary of the polygonal hole created by the bad triangles.
-        let mut polygon = Vec::new();
-        for triangle in bad_triangles.clone() {
-            for edge in triangle.edges.iter() {
-                let mut shared = false;
-                for other_triangle in bad_triangles.clone() {
-                    if triangle != other_triangle && other_triangle.has_edge(edge) {
-                            shared = true;
-                    }
-                }
+    Triangulation::from_valid_triangles(points, &triangulation.into_triangles())
+}
 
-                if !shared {
-                    polygon.push(edge.clone());
-                }
-            }
-        };
+/// Builds the regular (weighted Delaunay) triangulation of weighted points, the dual of their
+/// power diagram. Vertex i of the mesh is the point of sites[i]. Sites that are hidden, which have
+/// an empty cell in the power diagram, are not part of any triangle, so their vertices have no
+/// edges. Sites on the convex hull are never hidden.
+///
+/// Fails in the same cases as `dulaney_triangulation`, if two sites are at the same point (even
+/// with different weights), or if a weight is out of range.
+pub fn regular_triangulation(sites: &[WeightedPoint]) -> Result<Triangulation, VoronoiError> {
+
+    validate_weighted_sites(sites)?;
+
+    let points: Vec<Point> = sites.iter().map(|site| site.point).collect();
+    let weights: Vec<f64> = sites.iter().map(|site| site.weight).collect();
+
+    let order = order::brio(&points);
+    let c = first_triangle(&points, &order)?;
 
-        // Remove bad triangles from the triangulation.
-        for triangle in bad_triangles.iter() {
-            if let Some(triangle) = triangulation.iter().position(|t| t == triangle)  {
-                triangulation.remove(triangle);
+    let mut triangulation = BowyerWatson::weighted(&points, &weights, order[0], order[1], order[c]);
+    for (i, &point) in order.iter().enumerate().skip(2) {
+        if i != c {
+            triangulation.insert(point);
+        }
+    }
+
+    Triangulation::from_valid_triangles(&points, &triangulation.into_triangles())
+}
+
+/// The first triangle is the first two points in order, and the next point that isn't collinear
+/// with them. Returns the position of that third point in the order.
+fn first_triangle(points: &[Point], order: &[usize]) -> Result<usize, VoronoiError> {
+    if order.len() < 3 {
+        return Err(VoronoiError::DegenerateInput);
+    }
+
+    let (a, b) = (order[0], order[1]);
+    match order[2..].iter().position(|&c| orient2d(points[a], points[b], points[c]) != 0.0) {
+        Some(c) => Ok(c + 2),
+        None => Err(VoronoiError::DegenerateInput),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{jittered_grid, seeded_rng, uniform};
+    use crate::predicates::incircle;
+
+    /// Checks that no point is strictly inside the circumcircle of any triangle, and that there are
+    /// as many triangles as a triangulation of the points must have
+    fn assert_delaunay(points: &[Point], triangulation: &Triangulation) {
+        let mesh = &triangulation.mesh;
+        for face in mesh.faces() {
+            let corners: Vec<Point> = mesh.face_vertices(face).map(|vertex| mesh.point(vertex)).collect();
+            for &p in points {
+                assert!(incircle(corners[0], corners[1], corners[2], p) <= 0.0, "{:?} is inside the circumcircle of {:?}", p, corners);
             }
-        };
+        }
+
+        let hull = mesh.vertices().filter(|&vertex| mesh.is_boundary_vertex(vertex)).count();
+        assert_eq!(mesh.face_count(), 2 * points.len() - 2 - hull);
+    }
 
+    #[test]
+    fn empty_circumcircles() {
+        for seed in 0..20 {
+            let points = uniform(&mut seeded_rng(seed), 300, 0.0..1000.0, 0.0..1000.0);
+            assert_delaunay(&points, &dulaney_triangulation(&points).unwrap());
+        }
+    }
 
+    #[test]
+    fn cocircular_grid() {
+        // Every square of the grid has four cocircular corners, and rows and columns are collinear
+        let points = jittered_grid(&mut seeded_rng(0), 12, 9, 0.0..120.0, 0.0..90.0, 0.0);
+        let triangulation = dulaney_triangulation(&points).unwrap();
+        assert_delaunay(&points, &triangulation);
+        assert_eq!(triangulation.mesh.face_count(), 2 * 11 * 8);
+    }
 
-        // Re-triangulate the boundary of the polygonal hole.
-        for edge in polygon {
+    #[test]
+    fn cocircular_ring() {
+        // Twelve points exactly on the circle of radius 5, all with integer coordinates
+        let points: Vec<Point> = [
+            (5, 0), (4, 3), (3, 4), (0, 5), (-3, 4), (-4, 3),
+            (-5, 0), (-4, -3), (-3, -4), (0, -5), (3, -4), (4, -3),
+        ]
+        .iter()
+        .map(|&(x, y)| Point::new(x as f64, y as f64))
+        .collect();
+
+        let triangulation = dulaney_triangulation(&points).unwrap();
+        assert_delaunay(&points, &triangulation);
+        assert_eq!(triangulation.mesh.face_count(), points.len() - 2);
+        for triangle in triangulation.triangles.iter() {
+            assert!((triangle.circumradius - 5.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn collinear_points() {
+        let line: Vec<Point> = (0..10).map(|i| Point::new(i as f64, 3.0 * i as f64 + 1.0)).collect();
+        assert_eq!(dulaney_triangulation(&line).err(), Some(VoronoiError::DegenerateInput));
+        assert_eq!(dulaney_triangulation(&line[..2]).err(), Some(VoronoiError::DegenerateInput));
+
+        // A single point off the line is enough, wherever it comes in the order
+        let mut points = line.clone();
+        points.insert(5, Point::new(4.0, 0.0));
+        let triangulation = dulaney_triangulation(&points).unwrap();
+        assert_delaunay(&points, &triangulation);
+        assert_eq!(triangulation.mesh.face_count(), line.len() - 1);
+    }
 
-            let triangle = Triangle::new(edge.0, edge.1, point.clone());
-            triangulation.push(triangle);
-        };
+    #[test]
+    fn duplicate_points() {
+        let points = [Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(0.0, 3.0), Point::new(4.0, 0.0)];
+        assert_eq!(dulaney_triangulation(&points).err(), Some(VoronoiError::DuplicateSite { first: 1, duplicate: 3 }));
+        assert_eq!(dulaney_triangulation(&[]).err(), Some(VoronoiError::EmptyInput));
     }
 
-    // Cleanup: Remove any triangles that contain a vertex from the original super-triangle.
-    triangulation.into_iter().filter(|triangle| {
-        !triangle.has_vertex(super_triangle.p1) && 
-        !triangle.has_vertex(super_triangle.p2) && 
-        !triangle.has_vertex(super_triangle.p3) 
-    }).collect()
-    
-}
\ No newline at end of file
+    #[test]
+    fn triangles_meeting_at_a_corner() {
+        let points = [
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(-1.0, 0.0),
+            Point::new(-1.0, -1.0),
+        ];
+
+        let result = Triangulation::from_triangles(&points, &[[0, 1, 2], [0, 4, 3]]);
+        assert!(matches!(result, Err(VoronoiError::InvalidMesh(_))));
+    }
+}
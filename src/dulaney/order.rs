@@ -0,0 +1,81 @@
+//!
+//! The order points are inserted into the triangulation. Inserting points in a random order keeps
+//! the expected number of triangles created and destroyed low, while inserting neighboring points
+//! one after the other keeps each walk to the next point short. A biased randomized insertion
+//! order (BRIO) gets both: points are split into rounds at random, with each round about twice
+//! the size of the one before, and each round is sorted along a Hilbert curve.
+//!
+//! See Amenta, Choi and Rote, "Incremental Constructions con BRIO" (2003).
+//!
+
+use crate::point::Point;
+
+/// The Hilbert curve fills a grid of 2^ORDER by 2^ORDER cells
+const ORDER: u32 = 16;
+
+/// Returns the indices of the points in insertion order. The rounds are drawn from a fixed seed,
+/// so the same points always give the same triangulation.
+pub fn brio(points: &[Point]) -> Vec<usize> {
+    let (mut x_min, mut y_min) = (f64::INFINITY, f64::INFINITY);
+    let (mut x_max, mut y_max) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for point in points {
+        x_min = x_min.min(point.x);
+        y_min = y_min.min(point.y);
+        x_max = x_max.max(point.x);
+        y_max = y_max.max(point.y);
+    }
+
+    // Both axes share the same scale, so the curve doesn't favor either direction
+    let cells = ((1u32 << ORDER) - 1) as f64;
+    let extent = (x_max - x_min).max(y_max - y_min);
+    let scale = if extent > 0.0 { cells / extent } else { 0.0 };
+
+    let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut keys: Vec<(u32, u64, usize)> = points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            // xorshift64. Each trailing zero halves the chance of a point being in a round, so the
+            // rounds with the most zeroes are the smallest, and come first.
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let round = u32::MAX - seed.trailing_zeros();
+
+            let x = ((point.x - x_min) * scale) as u32;
+            let y = ((point.y - y_min) * scale) as u32;
+
+            (round, hilbert(x, y), i)
+        })
+        .collect();
+
+    keys.sort_unstable();
+    keys.into_iter().map(|(_, _, i)| i).collect()
+}
+
+/// The distance of the cell (x, y) along the Hilbert curve.
+/// See https://en.wikipedia.org/wiki/Hilbert_curve#Applications_and_mapping_algorithms
+fn hilbert(mut x: u32, mut y: u32) -> u64 {
+    let n = 1u32 << ORDER;
+    let mut d = 0u64;
+
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        d += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+
+        // Rotate the quadrant, so the curve inside it lines up with the rest
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        s /= 2;
+    }
+
+    d
+}
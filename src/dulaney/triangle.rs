@@ -1,14 +1,16 @@
-///
-/// Represents a single triangle in the triangulation. Each triangle is defined by three points. 
-/// 
-/// Author: Brendan McGuire
-/// Date: 18 July 2021
-/// 
-/// https://bren.app/voronoi/
-/// 
+//!
+//! Represents a single triangle in the triangulation. Each triangle is defined by three points. 
+//! 
+//! Author: Brendan McGuire
+//! Date: 18 July 2021
+//! 
+//! https://bren.app/voronoi/
+//! 
 
 use std::fmt::Display;
-use crate::{Point, point::Metric};
+use crate::Point;
+use crate::error::VoronoiError;
+use crate::predicates::{incircle, orient2d};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Triangle {
@@ -22,11 +24,19 @@ pub struct Triangle {
 }
 
 impl Triangle {
-    pub fn new(p1: Point, p2: Point, p3: Point) -> Triangle {
+    /// Constructs a triangle from three points, which are reordered to run counter-clockwise (in a
+    /// y-up coordinate system) if they don't already. Fails if the points are collinear.
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Result<Triangle, VoronoiError> {
 
-        let (circumcenter, circumradius) = Self::circumcircle(p1, p2, p3);
+        let (p2, p3) = if orient2d(p1, p2, p3) < 0.0 {
+            (p3, p2)
+        } else {
+            (p2, p3)
+        };
+
+        let (circumcenter, circumradius) = Self::circumcircle(p1, p2, p3)?;
 
-        Triangle {
+        Ok(Triangle {
             p1,
             p2,
             p3,
@@ -37,7 +47,7 @@ impl Triangle {
                 (p2, p3),
                 (p3, p1)
             ]
-        }
+        })
     }
 
     /// Computes the circumcenter of the triangle, which is defined as the point at which the
@@ -45,55 +55,41 @@ impl Triangle {
     /// defined as the center of the circumcircle, which is constructed from the 3 vertices of the
     /// triangle. 
     /// 
-    /// Note: this assumes that the three points are not collinear.
-    pub fn circumcircle(p1: Point, p2: Point, p3: Point) -> (Point, f64) {
-        
-        // See http://paulbourke.net/geometry/circlesphere/
-        
-        // Convert to f64 to avoid rounding errors, we'll convert back to i32 at the end
-        let (x_1, x_2, x_3): (f64, f64, f64) = (p1.x.into(), p2.x.into(), p3.x.into());
-        let (y_1, y_2, y_3): (f64, f64, f64) = (p1.y.into(), p2.y.into(), p3.y.into());
-
-
-        
-        let ma = (y_2 - y_1) / (x_2 - x_1);
-        let mb= (y_3 - y_2) / (x_3 - x_2);
-
-        // If either ma or mb is zero, then the perpendicular bisectors are vertical (infinite
-        // slope), which causes an error in computation. The easiest way to handle this is just
-        // rearrange the original points and retry the computation.
-        //
-        // If ma == mb, then the triangle is degenerate, and the circumcenter is undefined. The best
-        // way to handle this case is to panic until a Result chain can be established.
-            
-        if ma - mb == 0.0 {
-            panic!(
-                "Attempt to construct circumcircle of 3 collinear points!"
-            );
-        } else if ma == 0.0 {
-            Self::circumcircle(p3, p2, p1)
-        } else if mb == 0.0 {
-            Self::circumcircle(p2, p1, p3)
-        } else {
-            let x = ((ma * mb * (y_1 - y_3) + mb * (x_2 + x_3) - ma * (x_2 + x_3))) / (2.0 * (mb - ma));
-            let y = (-1.0 / ma) * (x - (x_1 + x_2) * 0.5) + (y_1 + y_2) * 0.5;
-    
-            let center = Point::new(x as i32, y as i32);
-            let radius = center.dist(&p1, Metric::Euclidean);
-    
-            (center, radius)
-        }       
-       
+    /// If the points are collinear, the circumcenter is undefined (infinitely far away), and this
+    /// returns VoronoiError::DegenerateInput.
+    pub fn circumcircle(p1: Point, p2: Point, p3: Point) -> Result<(Point, f64), VoronoiError> {
+
+        let orientation = orient2d(p1, p2, p3);
+        if orientation == 0.0 {
+            return Err(VoronoiError::DegenerateInput);
+        }
+
+        // Working relative to p1 keeps the numbers small, which keeps the rounding error small.
+        // The determinant is twice the signed area of the triangle, which orient2d already has.
+        // See https://en.wikipedia.org/wiki/Circumscribed_circle#Cartesian_coordinates_2
+        let (b_x, b_y) = (p2.x - p1.x, p2.y - p1.y);
+        let (c_x, c_y) = (p3.x - p1.x, p3.y - p1.y);
+
+        let d = 2.0 * orientation;
+        let (b_sq, c_sq) = (b_x * b_x + b_y * b_y, c_x * c_x + c_y * c_y);
+
+        let u_x = (c_y * b_sq - b_y * c_sq) / d;
+        let u_y = (b_x * c_sq - c_x * b_sq) / d;
+
+        let center = Point::new(p1.x + u_x, p1.y + u_y);
+        let radius = u_x.hypot(u_y);
+
+        Ok((center, radius))
     }
 
-    /// Returns true if the circumcircle of the triangle contains the point p. The circumcircle is
-    /// defined as the circle constructed by the 3 vertices of the triangle. See circumcenter above
-    /// for more details.
+    /// Returns true if the circumcircle of the triangle strictly contains the point p. The
+    /// circumcircle is defined as the circle constructed by the 3 vertices of the triangle. See
+    /// circumcenter above for more details.
+    ///
+    /// This uses the exact in-circle predicate rather than the computed circumcenter, so points on
+    /// (or extremely close to) the circle are classified correctly.
     pub fn circumcircle_contains(&self, p: &Point) -> bool {
-
-        // If the distance between p1 and the circumcenter is less than the distance between point
-        // and the circumcenter, then the point is inside the circumcircle.
-        self.circumradius > self.circumcenter.dist(p, Metric::Euclidean)
+        incircle(self.p1, self.p2, self.p3, *p) > 0.0
     }
 
     /// Returns true if the triangle has a vertex of p
@@ -101,9 +97,9 @@ impl Triangle {
         self.p1 == p || self.p2 == p || self.p3 == p
     }
 
-    /// Returns true if the triangle has an edge
+    /// Returns true if the triangle has an edge, in either direction
     pub fn has_edge(&self, edge: &(Point, Point)) -> bool {
-        self.edges.contains(edge)
+        self.edges.contains(edge) || self.edges.contains(&(edge.1, edge.0))
     }
 }
 
@@ -0,0 +1,125 @@
+//!
+//! Errors that can occur while building triangulations and diagrams, or while rendering them.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use std::{collections::HashMap, fmt::Display};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsValue;
+use crate::point::{Point, WeightedPoint};
+
+/// The largest coordinate magnitude accepted as input. The robust predicates raise coordinates to
+/// the fourth power, so anything much larger could overflow to infinity.
+pub const MAX_COORDINATE: f64 = 1e50;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VoronoiError {
+    /// The input has no sites at all
+    EmptyInput,
+
+    /// The sites don't span an area (fewer than 3 sites, or all of them collinear), so there are
+    /// no triangles to build from
+    DegenerateInput,
+
+    /// The same site appears twice
+    DuplicateSite { first: usize, duplicate: usize },
+
+    /// A coordinate is not finite, or its magnitude is larger than MAX_COORDINATE
+    OutOfRange { index: usize, point: Point },
+
+    /// A weight is not finite, or its magnitude is larger than MAX_COORDINATE squared
+    InvalidWeight { index: usize, weight: f64 },
+
+    /// The faces given to a mesh don't fit together: they overlap, or aren't all counter-clockwise
+    InvalidMesh(String),
+
+    /// A region to clip to is not a convex polygon
+    InvalidPolygon(String),
+
+    /// GeoJSON couldn't be read, or holds something other than points
+    InvalidGeoJson(String),
+
+    /// A file in one of Triangle's formats (.node, .ele, .poly, .edge) couldn't be read
+    InvalidTriangleFile(String),
+
+    /// Drawing to the canvas failed
+    Render(String),
+}
+
+impl Display for VoronoiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VoronoiError::EmptyInput => write!(f, "No sites were given"),
+            VoronoiError::DegenerateInput => write!(f, "Sites are collinear, or there are fewer than 3 of them"),
+            VoronoiError::DuplicateSite { first, duplicate } => {
+                write!(f, "Site {} is a duplicate of site {}", duplicate, first)
+            }
+            VoronoiError::OutOfRange { index, point } => {
+                write!(f, "Site {} at {} is out of range", index, point)
+            }
+            VoronoiError::InvalidWeight { index, weight } => {
+                write!(f, "Site {} has an invalid weight of {}", index, weight)
+            }
+            VoronoiError::InvalidMesh(message) => write!(f, "Invalid mesh: {}", message),
+            VoronoiError::InvalidPolygon(message) => write!(f, "Invalid polygon: {}", message),
+            VoronoiError::InvalidGeoJson(message) => write!(f, "Invalid GeoJSON: {}", message),
+            VoronoiError::InvalidTriangleFile(message) => write!(f, "Invalid Triangle file: {}", message),
+            VoronoiError::Render(message) => write!(f, "Rendering failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for VoronoiError {}
+
+#[cfg(feature = "wasm")]
+impl From<VoronoiError> for JsValue {
+    fn from(error: VoronoiError) -> JsValue {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl From<JsValue> for VoronoiError {
+    fn from(value: JsValue) -> VoronoiError {
+        VoronoiError::Render(value.as_string().unwrap_or_else(|| format!("{:?}", value)))
+    }
+}
+
+/// Checks that there is at least one site, that every coordinate is in range, and that no site is
+/// repeated.
+pub(crate) fn validate_sites(sites: &[Point]) -> Result<(), VoronoiError> {
+    if sites.is_empty() {
+        return Err(VoronoiError::EmptyInput);
+    }
+
+    let mut seen = HashMap::with_capacity(sites.len());
+    for (index, point) in sites.iter().enumerate() {
+        let in_range = |c: f64| c.is_finite() && c.abs() <= MAX_COORDINATE;
+        if !in_range(point.x) || !in_range(point.y) {
+            return Err(VoronoiError::OutOfRange { index, point: *point });
+        }
+
+        if let Some(&first) = seen.get(point) {
+            return Err(VoronoiError::DuplicateSite { first, duplicate: index });
+        }
+        seen.insert(*point, index);
+    }
+
+    Ok(())
+}
+
+/// Checks weighted sites the same way as validate_sites, and also checks that every weight is in
+/// range. Weights are compared with squared distances, so their limit is MAX_COORDINATE squared.
+pub(crate) fn validate_weighted_sites(sites: &[WeightedPoint]) -> Result<(), VoronoiError> {
+    let points: Vec<Point> = sites.iter().map(|site| site.point).collect();
+    validate_sites(&points)?;
+
+    for (index, site) in sites.iter().enumerate() {
+        if !site.weight.is_finite() || site.weight.abs() > MAX_COORDINATE * MAX_COORDINATE {
+            return Err(VoronoiError::InvalidWeight { index, weight: site.weight });
+        }
+    }
+
+    Ok(())
+}
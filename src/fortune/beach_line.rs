@@ -0,0 +1,294 @@
+//!
+//! The beach line of Fortune's algorithm: the sequence of parabolic arcs closest to the sweep line.
+//!
+//! Arcs are stored in a treap (a randomly balanced binary search tree) ordered from left to right,
+//! so finding the arc above a new site takes O(log n) expected time. The arcs are also threaded
+//! into a doubly linked list, which makes walking to the neighboring arcs O(1).
+//!
+//! Arcs are referred to by their index in the arena. Removed arcs are never reused, so an index
+//! stays valid (if dead) for the lifetime of the beach line.
+//!
+
+use crate::point::Point;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    site: usize,
+
+    /// Identifies the circle event currently predicted to remove this arc, if any
+    event: Option<usize>,
+
+    priority: u64,
+    parent: Option<usize>,
+    left: Option<usize>,
+    right: Option<usize>,
+
+    prev: Option<usize>,
+    next: Option<usize>,
+
+    alive: bool,
+}
+
+pub struct BeachLine {
+    nodes: Vec<Node>,
+    root: Option<usize>,
+    seed: u64,
+}
+
+impl BeachLine {
+    pub fn new() -> BeachLine {
+        BeachLine {
+            nodes: Vec::new(),
+            root: None,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    pub fn site(&self, arc: usize) -> usize {
+        self.nodes[arc].site
+    }
+
+    pub fn prev(&self, arc: usize) -> Option<usize> {
+        self.nodes[arc].prev
+    }
+
+    pub fn next(&self, arc: usize) -> Option<usize> {
+        self.nodes[arc].next
+    }
+
+    pub fn is_alive(&self, arc: usize) -> bool {
+        self.nodes[arc].alive
+    }
+
+    pub fn event(&self, arc: usize) -> Option<usize> {
+        self.nodes[arc].event
+    }
+
+    pub fn set_event(&mut self, arc: usize, event: Option<usize>) {
+        self.nodes[arc].event = event;
+    }
+
+    /// Inserts the very first arc. The beach line must be empty.
+    pub fn insert_first(&mut self, site: usize) -> usize {
+        let arc = self.allocate(site);
+        self.root = Some(arc);
+
+        arc
+    }
+
+    /// Finds the arc directly above x, when the sweep line is at y = directrix.
+    pub fn find(&self, x: f64, sites: &[Point], directrix: f64) -> Option<usize> {
+        let mut current = self.root?;
+
+        loop {
+            let node = &self.nodes[current];
+
+            if let Some(prev) = node.prev {
+                if x < breakpoint(sites[self.nodes[prev].site], sites[node.site], directrix) {
+                    match node.left {
+                        Some(left) => {
+                            current = left;
+                            continue;
+                        }
+                        None => return Some(current),
+                    }
+                }
+            }
+
+            if let Some(next) = node.next {
+                if x > breakpoint(sites[node.site], sites[self.nodes[next].site], directrix) {
+                    match node.right {
+                        Some(right) => {
+                            current = right;
+                            continue;
+                        }
+                        None => return Some(current),
+                    }
+                }
+            }
+
+            return Some(current);
+        }
+    }
+
+    /// Inserts a new arc for the site directly to the right of the given arc.
+    pub fn insert_after(&mut self, arc: usize, site: usize) -> usize {
+        let new = self.allocate(site);
+
+        // The new node goes in the leftmost position of the right subtree, or becomes the right
+        // child if there isn't one.
+        match self.nodes[arc].right {
+            None => {
+                self.nodes[arc].right = Some(new);
+                self.nodes[new].parent = Some(arc);
+            }
+            Some(mut current) => {
+                while let Some(left) = self.nodes[current].left {
+                    current = left;
+                }
+                self.nodes[current].left = Some(new);
+                self.nodes[new].parent = Some(current);
+            }
+        }
+
+        let next = self.nodes[arc].next;
+        self.nodes[new].prev = Some(arc);
+        self.nodes[new].next = next;
+        self.nodes[arc].next = Some(new);
+        if let Some(next) = next {
+            self.nodes[next].prev = Some(new);
+        }
+
+        // Restore the heap order of the priorities
+        while let Some(parent) = self.nodes[new].parent {
+            if self.nodes[parent].priority >= self.nodes[new].priority {
+                break;
+            }
+            self.rotate_up(new);
+        }
+
+        new
+    }
+
+    /// Removes an arc from the beach line.
+    pub fn remove(&mut self, arc: usize) {
+
+        // Rotate the node down until it is a leaf, then cut it off
+        loop {
+            let child = match (self.nodes[arc].left, self.nodes[arc].right) {
+                (None, None) => break,
+                (Some(left), None) => left,
+                (None, Some(right)) => right,
+                (Some(left), Some(right)) => {
+                    if self.nodes[left].priority > self.nodes[right].priority {
+                        left
+                    } else {
+                        right
+                    }
+                }
+            };
+            self.rotate_up(child);
+        }
+
+        match self.nodes[arc].parent {
+            None => self.root = None,
+            Some(parent) => {
+                if self.nodes[parent].left == Some(arc) {
+                    self.nodes[parent].left = None;
+                } else {
+                    self.nodes[parent].right = None;
+                }
+            }
+        }
+
+        let (prev, next) = (self.nodes[arc].prev, self.nodes[arc].next);
+        if let Some(prev) = prev {
+            self.nodes[prev].next = next;
+        }
+        if let Some(next) = next {
+            self.nodes[next].prev = prev;
+        }
+
+        let node = &mut self.nodes[arc];
+        node.alive = false;
+        node.event = None;
+        node.parent = None;
+        node.prev = None;
+        node.next = None;
+    }
+
+    fn allocate(&mut self, site: usize) -> usize {
+        // xorshift64*, which is plenty random for balancing and keeps the output deterministic
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        let priority = self.seed.wrapping_mul(0x2545_f491_4f6c_dd1d);
+
+        self.nodes.push(Node {
+            site,
+            event: None,
+            priority,
+            parent: None,
+            left: None,
+            right: None,
+            prev: None,
+            next: None,
+            alive: true,
+        });
+
+        self.nodes.len() - 1
+    }
+
+    /// Rotates a node above its parent, preserving the in-order sequence.
+    fn rotate_up(&mut self, node: usize) {
+        let parent = self.nodes[node].parent.expect("rotate_up on the root");
+        let grandparent = self.nodes[parent].parent;
+
+        if self.nodes[parent].left == Some(node) {
+            let moved = self.nodes[node].right;
+            self.nodes[parent].left = moved;
+            if let Some(moved) = moved {
+                self.nodes[moved].parent = Some(parent);
+            }
+            self.nodes[node].right = Some(parent);
+        } else {
+            let moved = self.nodes[node].left;
+            self.nodes[parent].right = moved;
+            if let Some(moved) = moved {
+                self.nodes[moved].parent = Some(parent);
+            }
+            self.nodes[node].left = Some(parent);
+        }
+
+        self.nodes[parent].parent = Some(node);
+        self.nodes[node].parent = grandparent;
+
+        match grandparent {
+            None => self.root = Some(node),
+            Some(grandparent) => {
+                if self.nodes[grandparent].left == Some(parent) {
+                    self.nodes[grandparent].left = Some(node);
+                } else {
+                    self.nodes[grandparent].right = Some(node);
+                }
+            }
+        }
+    }
+}
+
+/// Computes the x coordinate of the breakpoint between the arc of site p (on the left) and the arc
+/// of site q (on the right), when the sweep line is at y = directrix.
+///
+/// The parabola of a site (s_x, s_y) is y = ((x - s_x)² + s_y² - l²) / 2(s_y - l). Subtracting the
+/// two parabolas gives a quadratic, and the breakpoint we want is the root where p's parabola
+/// drops below q's.
+pub fn breakpoint(p: Point, q: Point, directrix: f64) -> f64 {
+    let (px, py) = (p.x, p.y);
+    let (qx, qy) = (q.x, q.y);
+
+    // A site on the sweep line has a degenerate parabola: a vertical ray straight up from it
+    if py == directrix && qy == directrix {
+        return (px + qx) / 2.0;
+    } else if py == directrix {
+        return px;
+    } else if qy == directrix {
+        return qx;
+    }
+
+    // Parabolas of sites at the same height are mirror images, and cross halfway between them
+    if py == qy {
+        return (px + qx) / 2.0;
+    }
+
+    let dp = 2.0 * (py - directrix);
+    let dq = 2.0 * (qy - directrix);
+
+    let a = 1.0 / dp - 1.0 / dq;
+    let b = -2.0 * (px / dp - qx / dq);
+    let c = (px * px + py * py - directrix * directrix) / dp
+        - (qx * qx + qy * qy - directrix * directrix) / dq;
+
+    let discriminant = (b * b - 4.0 * a * c).max(0.0);
+
+    (-b - discriminant.sqrt()) / (2.0 * a)
+}
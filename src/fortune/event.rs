@@ -0,0 +1,48 @@
+//!
+//! Circle events for Fortune's algorithm. A circle event predicts the moment an arc will vanish
+//! from the beach line, which happens when the sweep line reaches the bottom of the circle through
+//! the arc's site and the sites of its two neighbors.
+//!
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CircleEvent {
+    /// Position of the sweep line when the event happens: the bottom of the circle
+    pub y: f64,
+
+    /// The center of the circle, which becomes a vertex of the diagram
+    pub center: (f64, f64),
+
+    /// The arc that will disappear
+    pub arc: usize,
+
+    /// Unique identifier, used to tell if the arc still expects this event
+    pub id: usize,
+}
+
+// BinaryHeap is a max heap, so events are ordered in reverse: the event with the smallest y (then
+// smallest x) is the greatest.
+impl Ord for CircleEvent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .y
+            .total_cmp(&self.y)
+            .then_with(|| other.center.0.total_cmp(&self.center.0))
+            .then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+impl PartialOrd for CircleEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for CircleEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for CircleEvent {}
@@ -0,0 +1,252 @@
+//!
+//! Fortune's Algorithm, a sweep line construction of the Voronoi diagram in O(n log n) time.
+//!
+//! A horizontal line sweeps across the plane, towards increasing y. Behind it, every point that is
+//! closer to one of the sites than to the sweep line is already settled, because no site the line
+//! reaches later can be closer. The boundary of the settled area is the beach line, made of one
+//! parabolic arc per site. Two kinds of events change the beach line:
+//!
+//! - Site events, when the sweep line reaches a new site. A new arc for the site splits the arc
+//!   above it in two.
+//! - Circle events, when an arc shrinks to nothing between its neighbors. The point where it
+//!   vanishes is equally far from three sites, which makes it a vertex of the Voronoi diagram.
+//!
+//! Each circle event finds one Dulaney triangle, so the cells are assembled from the same
+//! triangulation mesh as the Bowyer–Watson path, and the two can be compared directly.
+//!
+//! https://bren.app/voronoi/
+//!
+
+mod beach_line;
+mod event;
+
+use std::collections::BinaryHeap;
+use crate::dulaney::Triangulation;
+use crate::error::{validate_sites, VoronoiError};
+use crate::point::Point;
+use crate::predicates::orient2d;
+use crate::voronoi::VoronoiDiagram;
+use beach_line::BeachLine;
+use event::CircleEvent;
+
+/// Computes the Voronoi diagram of the sites with Fortune's algorithm. Fails in the same cases as
+/// `dulaney_triangulation`.
+pub fn fortunes_algorithm(sites: &[Point]) -> Result<VoronoiDiagram, VoronoiError> {
+
+    validate_sites(sites)?;
+
+    // Sites are reached from top to bottom, and from left to right along the same row
+    let mut order: Vec<usize> = (0..sites.len()).collect();
+    order.sort_by(|&i, &j| sites[i].y.total_cmp(&sites[j].y).then(sites[i].x.total_cmp(&sites[j].x)));
+
+    let mut sweep = Sweep {
+        sites,
+        beach_line: BeachLine::new(),
+        events: BinaryHeap::new(),
+        triangles: Vec::new(),
+        next_event: 0,
+    };
+
+    let mut site_events = order.into_iter().peekable();
+    loop {
+        // Take whichever event comes first. Circle events win ties, so that vertices are finished
+        // before a site lands on them.
+        let circle_first = match (site_events.peek(), sweep.events.peek()) {
+            (None, None) => break,
+            (Some(_), None) => false,
+            (None, Some(_)) => true,
+            (Some(&site), Some(event)) => event.y <= sites[site].y,
+        };
+
+        if circle_first {
+            let event = sweep.events.pop().unwrap();
+            sweep.circle_event(event);
+        } else {
+            let site = site_events.next().unwrap();
+            sweep.site_event(site);
+        }
+    }
+
+    // Without any vertices, the sites must have been collinear
+    if sweep.triangles.is_empty() {
+        return Err(VoronoiError::DegenerateInput);
+    }
+
+    let triangulation = Triangulation::from_valid_triangles(sites, &sweep.triangles)?;
+    Ok(VoronoiDiagram::from_triangulation(triangulation))
+}
+
+/// The state of the sweep
+struct Sweep<'a> {
+    sites: &'a [Point],
+    beach_line: BeachLine,
+    events: BinaryHeap<CircleEvent>,
+
+    /// The Dulaney triangles found so far, as the indices of their sites
+    triangles: Vec<[usize; 3]>,
+
+    next_event: usize,
+}
+
+impl Sweep<'_> {
+    fn site_event(&mut self, site: usize) {
+        let point = self.sites[site];
+        let (x, y) = (point.x, point.y);
+
+        let arc = match self.beach_line.find(x, self.sites, y) {
+            Some(arc) => arc,
+            None => {
+                self.beach_line.insert_first(site);
+                return;
+            }
+        };
+
+        let above = self.beach_line.site(arc);
+        if self.sites[above].y == point.y {
+
+            // Only possible while every site so far is on the first row. Both parabolas are
+            // vertical rays, so the new arc goes beside the old one rather than splitting it.
+            self.beach_line.insert_after(arc, site);
+            self.check_circle(arc);
+        } else {
+
+            // Split the arc above into two, with the new arc between them
+            let middle = self.beach_line.insert_after(arc, site);
+            let right = self.beach_line.insert_after(middle, above);
+
+            self.check_circle(arc);
+            self.check_circle(right);
+        }
+    }
+
+    fn circle_event(&mut self, event: CircleEvent) {
+        let arc = event.arc;
+
+        // Skip events that were invalidated after they were scheduled
+        if !self.beach_line.is_alive(arc) || self.beach_line.event(arc) != Some(event.id) {
+            return;
+        }
+
+        let (prev, next) = match (self.beach_line.prev(arc), self.beach_line.next(arc)) {
+            (Some(prev), Some(next)) => (prev, next),
+            _ => return,
+        };
+
+        self.triangles.push([self.beach_line.site(prev), self.beach_line.site(arc), self.beach_line.site(next)]);
+
+        self.beach_line.remove(arc);
+        self.check_circle(prev);
+        self.check_circle(next);
+    }
+
+    /// Schedules the circle event for an arc, if its breakpoints are converging. The neighbors of
+    /// the arc have changed, so any event that was previously scheduled is no longer valid.
+    fn check_circle(&mut self, arc: usize) {
+        self.beach_line.set_event(arc, None);
+
+        let (prev, next) = match (self.beach_line.prev(arc), self.beach_line.next(arc)) {
+            (Some(prev), Some(next)) => (prev, next),
+            _ => return,
+        };
+
+        let a = self.sites[self.beach_line.site(prev)];
+        let b = self.sites[self.beach_line.site(arc)];
+        let c = self.sites[self.beach_line.site(next)];
+
+        let (ax, ay) = (a.x, a.y);
+        let (bx, by) = (b.x, b.y);
+        let (cx, cy) = (c.x, c.y);
+
+        // The breakpoints only converge if the sites turn counter-clockwise. This also rules out
+        // collinear sites, and the same site on both sides of the arc.
+        let orientation = orient2d(a, b, c);
+        if orientation <= 0.0 {
+            return;
+        }
+        let d = 2.0 * orientation;
+
+        let (b_sq, c_sq) = ((bx - ax).powi(2) + (by - ay).powi(2), (cx - ax).powi(2) + (cy - ay).powi(2));
+        let ux = ((cy - ay) * b_sq - (by - ay) * c_sq) / d;
+        let uy = ((bx - ax) * c_sq - (cx - ax) * b_sq) / d;
+
+        let center = (ax + ux, ay + uy);
+        let radius = (ux * ux + uy * uy).sqrt();
+
+        let id = self.next_event;
+        self.next_event += 1;
+
+        self.beach_line.set_event(arc, Some(id));
+        self.events.push(CircleEvent {
+            y: center.1 + radius,
+            center,
+            arc,
+            id,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dulaney::dulaney_triangulation;
+    use crate::generators::{circle, gaussian_clusters, jittered_grid, seeded_rng, uniform};
+    use crate::predicates::incircle;
+
+    /// The corners of each triangle, each starting from its smallest corner, in order
+    fn triangle_set(triangulation: &Triangulation) -> Vec<[usize; 3]> {
+        let mesh = &triangulation.mesh;
+        let mut triangles: Vec<[usize; 3]> = mesh
+            .faces()
+            .map(|face| {
+                let corners: Vec<usize> = mesh.face_vertices(face).map(|vertex| vertex.0).collect();
+                let first = (0..3).min_by_key(|&i| corners[i]).unwrap();
+                [corners[first], corners[(first + 1) % 3], corners[(first + 2) % 3]]
+            })
+            .collect();
+        triangles.sort_unstable();
+
+        triangles
+    }
+
+    #[test]
+    fn same_triangles_as_bowyer_watson() {
+        for seed in 0..50 {
+            let mut rng = seeded_rng(seed);
+            let sites = if seed % 2 == 0 {
+                uniform(&mut rng, 200, 0.0..1000.0, 0.0..1000.0)
+            } else {
+                gaussian_clusters(&mut rng, 200, 4, 30.0, 0.0..1000.0, 0.0..1000.0)
+            };
+
+            let fortune = fortunes_algorithm(&sites).unwrap();
+            let bowyer_watson = dulaney_triangulation(&sites).unwrap();
+            assert_eq!(triangle_set(&fortune.triangulation), triangle_set(&bowyer_watson), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn cocircular_sites() {
+        // Every square of the grid has four cocircular corners, so the triangles can't be compared
+        // with Bowyer–Watson's, only checked to be Dulaney and to cover the grid
+        let sites = jittered_grid(&mut seeded_rng(0), 12, 9, 0.0..120.0, 0.0..90.0, 0.0);
+        let mesh = fortunes_algorithm(&sites).unwrap().triangulation.mesh;
+        assert_eq!(mesh.face_count(), 2 * 11 * 8);
+        for face in mesh.faces() {
+            let corners: Vec<Point> = mesh.face_vertices(face).map(|vertex| mesh.point(vertex)).collect();
+            assert!(sites.iter().all(|&p| incircle(corners[0], corners[1], corners[2], p) <= 0.0));
+        }
+
+        let ring = circle(16, Point::new(50.0, 50.0), 40.0, 0.0);
+        assert_eq!(fortunes_algorithm(&ring).unwrap().triangulation.mesh.face_count(), ring.len() - 2);
+    }
+
+    #[test]
+    fn rejects_the_same_inputs() {
+        let duplicate = [Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(0.0, 0.0)];
+        assert_eq!(fortunes_algorithm(&duplicate).err(), Some(VoronoiError::DuplicateSite { first: 0, duplicate: 2 }));
+
+        let collinear: Vec<Point> = (0..5).map(|i| Point::new(i as f64, 2.0 * i as f64)).collect();
+        assert_eq!(fortunes_algorithm(&collinear).err(), Some(VoronoiError::DegenerateInput));
+        assert_eq!(fortunes_algorithm(&[]).err(), Some(VoronoiError::EmptyInput));
+    }
+}
@@ -0,0 +1,255 @@
+//!
+//! Generators for sets of sites. The random ones take any random number generator, so a run can be
+//! repeated exactly by passing in one made with `seeded_rng` and the same seed. The low-discrepancy
+//! sequences (Halton and Sobol) aren't random at all: they fill a rectangle evenly, without the
+//! clumps and gaps of uniform random points, and always give the same points.
+//!
+//! Points on a circle are a stress test rather than a useful layout: in exact arithmetic every four
+//! of them are cocircular, which is the degenerate case for Delaunay triangulations. Rounding moves
+//! them slightly off the circle, so they are only nearly cocircular, which is just as hard.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use std::f64::consts::PI;
+use std::ops::Range;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use crate::point::Point;
+
+/// How many times `gaussian_clusters` tries to place a point inside the rectangle. Running out of
+/// tries takes a deviation far larger than the rectangle.
+const CLUSTER_TRIES: usize = 100;
+
+/// The random number generator made by `seeded_rng`. It gives the same numbers for the same seed
+/// on every platform, and in every version of this crate.
+pub type SeededRng = ChaCha8Rng;
+
+/// A random number generator that always gives the same numbers for the same seed
+pub fn seeded_rng(seed: u64) -> SeededRng {
+    SeededRng::seed_from_u64(seed)
+}
+
+/// `n` points spread uniformly at random over a rectangle
+pub fn uniform<R: Rng + ?Sized>(rng: &mut R, n: usize, x: Range<f64>, y: Range<f64>) -> Vec<Point> {
+    (0..n).map(|_| Point::random(rng, x.clone(), y.clone())).collect()
+}
+
+/// One point in each cell of a `columns` by `rows` grid over a rectangle. With a jitter of 0 each
+/// point is at the center of its cell, and with a jitter of 1 it is anywhere in its cell, so the
+/// points are random but never clump together. The points are in order, row by row.
+pub fn jittered_grid<R: Rng + ?Sized>(rng: &mut R, columns: usize, rows: usize, x: Range<f64>, y: Range<f64>, jitter: f64) -> Vec<Point> {
+    let width = (x.end - x.start) / columns as f64;
+    let height = (y.end - y.start) / rows as f64;
+    let jitter = jitter.clamp(0.0, 1.0);
+
+    let mut points = Vec::with_capacity(columns * rows);
+    for row in 0..rows {
+        for column in 0..columns {
+            let u: f64 = rng.gen();
+            let v: f64 = rng.gen();
+
+            points.push(Point::new(
+                x.start + width * (column as f64 + 0.5 + jitter * (u - 0.5)),
+                y.start + height * (row as f64 + 0.5 + jitter * (v - 0.5)),
+            ));
+        }
+    }
+
+    points
+}
+
+/// `n` points in clusters, with the centers of the clusters spread uniformly over a rectangle.
+/// Each point picks a cluster at random, and is normally distributed around its center with the
+/// given standard deviation. Points that land outside the rectangle are tried again, so all of
+/// them are inside it; a point that misses too many times in a row goes at its cluster's center.
+///
+/// Panics if either range is empty or not finite, or if the deviation is negative or not finite.
+pub fn gaussian_clusters<R: Rng + ?Sized>(rng: &mut R, n: usize, clusters: usize, deviation: f64, x: Range<f64>, y: Range<f64>) -> Vec<Point> {
+    let valid = |range: &Range<f64>| range.start.is_finite() && range.end.is_finite() && range.start < range.end;
+    assert!(valid(&x) && valid(&y), "the rectangle {:?} by {:?} is empty or not finite", x, y);
+    assert!(deviation.is_finite() && deviation >= 0.0, "the deviation {} is negative or not finite", deviation);
+
+    let centers = uniform(rng, clusters.max(1), x.clone(), y.clone());
+
+    (0..n)
+        .map(|_| {
+            let center = centers[rng.gen_range(0..centers.len())];
+
+            for _ in 0..CLUSTER_TRIES {
+                let (u, v) = normal_pair(rng);
+                let p = Point::new(center.x + deviation * u, center.y + deviation * v);
+                if x.contains(&p.x) && y.contains(&p.y) {
+                    return p;
+                }
+            }
+
+            center
+        })
+        .collect()
+}
+
+/// The first `n` points of the Halton sequence in bases 2 and 3, scaled to a rectangle. The
+/// sequence starts at index 1, since index 0 is the corner of the rectangle.
+pub fn halton(n: usize, x: Range<f64>, y: Range<f64>) -> Vec<Point> {
+    (1..=n as u64)
+        .map(|i| Point::new(lerp(&x, radical_inverse(i, 2)), lerp(&y, radical_inverse(i, 3))))
+        .collect()
+}
+
+/// The first `n` points of the two dimensional Sobol sequence, scaled to a rectangle. Like
+/// `halton`, this skips the point at the corner. The sequence repeats after 2³² points.
+pub fn sobol(n: usize, x: Range<f64>, y: Range<f64>) -> Vec<Point> {
+    // The first dimension's direction numbers are single bits, which reverses the bits of the
+    // index. The second's come from the polynomial x + 1, so each is the one before shifted down,
+    // xored with itself.
+    let mut directions = [[0u32; 32]; 2];
+    for bit in 0..32 {
+        directions[0][bit] = 1 << (31 - bit);
+        directions[1][bit] = if bit == 0 { 1 << 31 } else { directions[1][bit - 1] ^ (directions[1][bit - 1] >> 1) };
+    }
+
+    // Walk the sequence in Gray code order, which changes one bit (and one direction) per step
+    let (mut u, mut v) = (0u32, 0u32);
+    (1..=n as u64)
+        .map(|i| {
+            let bit = (i - 1).trailing_ones() as usize % 32;
+            u ^= directions[0][bit];
+            v ^= directions[1][bit];
+
+            let scale = 1.0 / (1u64 << 32) as f64;
+            Point::new(lerp(&x, u as f64 * scale), lerp(&y, v as f64 * scale))
+        })
+        .collect()
+}
+
+/// `n` points evenly spaced around a circle, starting at the angle `start` (in radians)
+pub fn circle(n: usize, center: Point, radius: f64, start: f64) -> Vec<Point> {
+    (0..n)
+        .map(|i| {
+            let angle = start + 2.0 * PI * i as f64 / n as f64;
+            Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin())
+        })
+        .collect()
+}
+
+/// Two independent standard normal numbers, with the Box–Muller transform
+fn normal_pair<R: Rng + ?Sized>(rng: &mut R) -> (f64, f64) {
+    // 1 - gen() is never 0, so its logarithm is finite
+    let radius = (-2.0 * (1.0 - rng.gen::<f64>()).ln()).sqrt();
+    let angle = 2.0 * PI * rng.gen::<f64>();
+
+    (radius * angle.cos(), radius * angle.sin())
+}
+
+/// The digits of i in the given base, mirrored around the decimal point
+fn radical_inverse(mut i: u64, base: u64) -> f64 {
+    let (mut inverse, mut scale) = (0.0, 1.0 / base as f64);
+    while i > 0 {
+        inverse += (i % base) as f64 * scale;
+        i /= base;
+        scale /= base as f64;
+    }
+
+    inverse
+}
+
+/// The point t of the way along a range
+fn lerp(range: &Range<f64>, t: f64) -> f64 {
+    range.start + (range.end - range.start) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inside(points: &[Point], x: Range<f64>, y: Range<f64>) -> bool {
+        points.iter().all(|p| x.contains(&p.x) && y.contains(&p.y))
+    }
+
+    #[test]
+    fn same_seed_same_points() {
+        let generate = |seed: u64| {
+            let mut rng = seeded_rng(seed);
+            let mut points = uniform(&mut rng, 20, 0.0..10.0, 0.0..10.0);
+            points.extend(jittered_grid(&mut rng, 4, 3, 0.0..10.0, 0.0..10.0, 1.0));
+            points.extend(gaussian_clusters(&mut rng, 20, 3, 1.0, 0.0..10.0, 0.0..10.0));
+            points
+        };
+
+        assert_eq!(generate(42), generate(42));
+        assert_ne!(generate(42), generate(43));
+    }
+
+    #[test]
+    fn points_are_in_range() {
+        let mut rng = seeded_rng(1);
+        let (x, y) = (-5.0..20.0, 100.0..101.0);
+
+        assert!(inside(&uniform(&mut rng, 500, x.clone(), y.clone()), x.clone(), y.clone()));
+        assert!(inside(&halton(500, x.clone(), y.clone()), x.clone(), y.clone()));
+        assert!(inside(&sobol(500, x.clone(), y.clone()), x.clone(), y.clone()));
+        assert!(inside(&gaussian_clusters(&mut rng, 500, 4, 3.0, x.clone(), y.clone()), x.clone(), y.clone()));
+
+        // Each point stays in its own cell, row by row
+        let grid = jittered_grid(&mut rng, 5, 4, 0.0..5.0, 0.0..4.0, 1.0);
+        assert_eq!(grid.len(), 20);
+        for (i, p) in grid.iter().enumerate() {
+            assert_eq!((p.x.floor() as usize, p.y.floor() as usize), (i % 5, i / 5));
+        }
+
+        // A cluster far wider than the rectangle runs out of tries, but stays inside it
+        let wide = gaussian_clusters(&mut rng, 50, 2, 1e12, 0.0..1.0, 0.0..1.0);
+        assert!(inside(&wide, 0.0..1.0, 0.0..1.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn infinite_deviation() {
+        gaussian_clusters(&mut seeded_rng(0), 10, 2, f64::INFINITY, 0.0..1.0, 0.0..1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_rectangle() {
+        gaussian_clusters(&mut seeded_rng(0), 10, 2, 1.0, 0.0..1.0, 3.0..3.0);
+    }
+
+    #[test]
+    fn halton_values() {
+        let points = halton(5, 0.0..1.0, 0.0..1.0);
+        let xs: Vec<f64> = points.iter().map(|p| p.x).collect();
+        let ys: Vec<f64> = points.iter().map(|p| p.y).collect();
+
+        assert_eq!(xs, [0.5, 0.25, 0.75, 0.125, 0.625]);
+        let expected = [1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0, 4.0 / 9.0, 7.0 / 9.0];
+        for (y, expected) in ys.iter().zip(expected.iter()) {
+            assert!((y - expected).abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn sobol_values() {
+        // The sequence in Gray code order, after the point at the origin
+        let expected = [
+            (0.5, 0.5),
+            (0.75, 0.25),
+            (0.25, 0.75),
+            (0.375, 0.375),
+            (0.875, 0.875),
+            (0.625, 0.125),
+            (0.125, 0.625),
+        ];
+        let points: Vec<(f64, f64)> = sobol(7, 0.0..1.0, 0.0..1.0).iter().map(|p| (p.x, p.y)).collect();
+
+        assert_eq!(points, expected);
+    }
+
+    #[test]
+    fn circle_points() {
+        let center = Point::new(3.0, -2.0);
+        for p in circle(12, center, 5.0, 0.3) {
+            assert!(((p.x - center.x).hypot(p.y - center.y) - 5.0).abs() < 1e-12);
+        }
+    }
+}
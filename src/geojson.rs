@@ -0,0 +1,341 @@
+//!
+//! Reads sites from GeoJSON, and writes Voronoi cells and Delaunay triangles back out as GeoJSON,
+//! so diagrams can be made from (and taken back to) GIS tools. A typical use is service areas:
+//! read in a set of locations, and get back the cell around each one, with the properties of its
+//! location.
+//!
+//! Coordinates are used as they are, as x (longitude) and y (latitude), so diagrams are made in the
+//! flat plane of the coordinates. Over small areas that is close enough; over larger ones, project
+//! the sites first. Polygons are written counter-clockwise, as RFC 7946 asks.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use serde_json::{json, Map, Value};
+use crate::dulaney::Triangulation;
+use crate::error::VoronoiError;
+use crate::point::Point;
+use crate::polygon::{area, ConvexPolygon};
+use crate::voronoi::VoronoiDiagram;
+
+/// The properties of a feature
+pub type Properties = Map<String, Value>;
+
+/// Sites read from GeoJSON, each with the properties of the feature it came from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeoSites {
+    pub sites: Vec<Point>,
+
+    /// The properties for each site, in the same order. Features with null properties have an
+    /// empty set.
+    pub properties: Vec<Properties>,
+}
+
+/// Reads sites from a GeoJSON FeatureCollection of Point and MultiPoint features. Every point of
+/// a MultiPoint becomes a site, with the properties of its feature. A single Feature, or a bare
+/// Point or MultiPoint geometry, is read the same way. Features without a geometry are skipped.
+///
+/// Fails if the text isn't JSON, or any feature has a geometry other than a Point or MultiPoint.
+pub fn parse_sites(text: &str) -> Result<GeoSites, VoronoiError> {
+    let geojson: Value = serde_json::from_str(text).map_err(|error| VoronoiError::InvalidGeoJson(error.to_string()))?;
+    let mut sites = GeoSites::default();
+
+    match member_str(&geojson, "type")? {
+        "FeatureCollection" => {
+            let features = geojson
+                .get("features")
+                .and_then(Value::as_array)
+                .ok_or_else(|| invalid("a FeatureCollection has no features array"))?;
+            for feature in features {
+                read_feature(feature, &mut sites)?;
+            }
+        }
+        "Feature" => read_feature(&geojson, &mut sites)?,
+        _ => read_geometry(&geojson, &Properties::new(), &mut sites)?,
+    }
+
+    Ok(sites)
+}
+
+/// The cells of a diagram, clipped to a region, as a FeatureCollection of Polygon features, one
+/// for each site, in order. Each cell has the properties of its site, if there are any. Cells that
+/// are clipped away entirely have a null geometry, so features still line up with sites.
+pub fn cells_to_geojson(diagram: &VoronoiDiagram, region: &ConvexPolygon, properties: &[Properties]) -> Value {
+    let features: Vec<Value> = diagram
+        .clip(region)
+        .iter()
+        .enumerate()
+        .map(|(site, cell)| {
+            let geometry = if cell.len() < 3 { Value::Null } else { polygon(cell) };
+            feature(geometry, properties.get(site).cloned().unwrap_or_default())
+        })
+        .collect();
+
+    feature_collection(features)
+}
+
+/// The triangles of a triangulation as a FeatureCollection of Polygon features. Each has a "sites"
+/// property, with the indices of its three corners.
+pub fn triangles_to_geojson(triangulation: &Triangulation) -> Value {
+    let mesh = &triangulation.mesh;
+    let features: Vec<Value> = mesh
+        .faces()
+        .map(|face| {
+            let corners: Vec<usize> = mesh.face_vertices(face).map(|vertex| vertex.0).collect();
+            let points: Vec<Point> = mesh.face_vertices(face).map(|vertex| mesh.point(vertex)).collect();
+
+            let mut properties = Properties::new();
+            properties.insert("sites".to_string(), json!(corners));
+            feature(polygon(&points), properties)
+        })
+        .collect();
+
+    feature_collection(features)
+}
+
+/// The edges of a triangulation as a FeatureCollection of LineString features, one for each edge.
+/// Each has a "sites" property, with the indices of its two ends.
+pub fn edges_to_geojson(triangulation: &Triangulation) -> Value {
+    let mesh = &triangulation.mesh;
+    let features: Vec<Value> = mesh
+        .edges()
+        .filter_map(|edge| {
+            let (a, b) = (mesh.origin(edge), mesh.destination(edge));
+
+            // Each edge is two half-edges, so only write one of them
+            if a > b {
+                return None;
+            }
+
+            let mut properties = Properties::new();
+            properties.insert("sites".to_string(), json!([a.0, b.0]));
+            let geometry = json!({
+                "type": "LineString",
+                "coordinates": [position(mesh.point(a)), position(mesh.point(b))],
+            });
+            Some(feature(geometry, properties))
+        })
+        .collect();
+
+    feature_collection(features)
+}
+
+/// Adds the sites in a feature
+fn read_feature(feature: &Value, sites: &mut GeoSites) -> Result<(), VoronoiError> {
+    if member_str(feature, "type")? != "Feature" {
+        return Err(invalid("a FeatureCollection holds something other than a Feature"));
+    }
+
+    let properties = match feature.get("properties") {
+        None | Some(Value::Null) => Properties::new(),
+        Some(Value::Object(properties)) => properties.clone(),
+        Some(_) => return Err(invalid("a feature's properties are not an object")),
+    };
+
+    match feature.get("geometry") {
+        None | Some(Value::Null) => Ok(()),
+        Some(geometry) => read_geometry(geometry, &properties, sites),
+    }
+}
+
+/// Adds the sites in a Point or MultiPoint geometry, each with the properties
+fn read_geometry(geometry: &Value, properties: &Properties, sites: &mut GeoSites) -> Result<(), VoronoiError> {
+    let coordinates = geometry.get("coordinates").ok_or_else(|| invalid("a geometry has no coordinates"))?;
+
+    let points = match member_str(geometry, "type")? {
+        "Point" => vec![read_position(coordinates)?],
+        "MultiPoint" => coordinates
+            .as_array()
+            .ok_or_else(|| invalid("a MultiPoint's coordinates are not an array"))?
+            .iter()
+            .map(read_position)
+            .collect::<Result<Vec<_>, _>>()?,
+        other => return Err(invalid(&format!("a {} can't be read as sites, only a Point or MultiPoint", other))),
+    };
+
+    for point in points {
+        sites.sites.push(point);
+        sites.properties.push(properties.clone());
+    }
+
+    Ok(())
+}
+
+/// A position, [x, y], with any altitude after them ignored
+fn read_position(position: &Value) -> Result<Point, VoronoiError> {
+    match position.as_array().map(|position| position.as_slice()) {
+        Some([x, y, ..]) => match (x.as_f64(), y.as_f64()) {
+            (Some(x), Some(y)) => Ok(Point::new(x, y)),
+            _ => Err(invalid(&format!("the position {} is not made of numbers", position))),
+        },
+        _ => Err(invalid(&format!("the position {} does not have two coordinates", position))),
+    }
+}
+
+/// A member of an object that has to be a string, like "type"
+fn member_str<'a>(object: &'a Value, member: &str) -> Result<&'a str, VoronoiError> {
+    object
+        .get(member)
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid(&format!("an object has no \"{}\"", member)))
+}
+
+fn invalid(message: &str) -> VoronoiError {
+    VoronoiError::InvalidGeoJson(message.to_string())
+}
+
+/// A Polygon geometry, with its ring counter-clockwise and closed
+fn polygon(points: &[Point]) -> Value {
+    let mut ring: Vec<Value> = points.iter().map(|&p| position(p)).collect();
+    if area(points) < 0.0 {
+        ring.reverse();
+    }
+    ring.push(ring[0].clone());
+
+    json!({ "type": "Polygon", "coordinates": [ring] })
+}
+
+fn position(p: Point) -> Value {
+    json!([p.x, p.y])
+}
+
+fn feature(geometry: Value, properties: Properties) -> Value {
+    json!({ "type": "Feature", "geometry": geometry, "properties": properties })
+}
+
+fn feature_collection(features: Vec<Value>) -> Value {
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn points(ring: &Value) -> Vec<Point> {
+        ring.as_array()
+            .unwrap()
+            .iter()
+            .map(|position| Point::new(position[0].as_f64().unwrap(), position[1].as_f64().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn points_and_multipoints() {
+        let text = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                { "type": "Feature", "geometry": { "type": "Point", "coordinates": [1, 2] }, "properties": { "name": "a" } },
+                { "type": "Feature", "geometry": { "type": "MultiPoint", "coordinates": [[3, 4, 100], [5.5, -6]] }, "properties": { "name": "b" } },
+                { "type": "Feature", "geometry": null, "properties": { "name": "c" } },
+                { "type": "Feature", "geometry": { "type": "Point", "coordinates": [7, 8] }, "properties": null }
+            ]
+        }"#;
+        let sites = parse_sites(text).unwrap();
+
+        assert_eq!(sites.sites, vec![Point::new(1.0, 2.0), Point::new(3.0, 4.0), Point::new(5.5, -6.0), Point::new(7.0, 8.0)]);
+        let names: Vec<Option<&str>> = sites.properties.iter().map(|properties| properties.get("name").and_then(Value::as_str)).collect();
+        assert_eq!(names, vec![Some("a"), Some("b"), Some("b"), None]);
+        assert!(sites.properties[3].is_empty());
+    }
+
+    #[test]
+    fn single_features_and_geometries() {
+        let feature = r#"{ "type": "Feature", "geometry": { "type": "Point", "coordinates": [1, 2] }, "properties": { "id": 7 } }"#;
+        let sites = parse_sites(feature).unwrap();
+        assert_eq!(sites.sites, vec![Point::new(1.0, 2.0)]);
+        assert_eq!(sites.properties[0].get("id"), Some(&json!(7)));
+
+        let geometry = r#"{ "type": "MultiPoint", "coordinates": [[1, 2], [3, 4]] }"#;
+        let sites = parse_sites(geometry).unwrap();
+        assert_eq!(sites.sites.len(), 2);
+        assert!(sites.properties.iter().all(Properties::is_empty));
+    }
+
+    #[test]
+    fn invalid_sites() {
+        let invalid = |text: &str| matches!(parse_sites(text), Err(VoronoiError::InvalidGeoJson(_)));
+
+        assert!(invalid("not json"));
+        assert!(invalid(r#"{ "type": "LineString", "coordinates": [[0, 0], [1, 1]] }"#));
+        assert!(invalid(r#"{ "type": "Point", "coordinates": [1] }"#));
+        assert!(invalid(r#"{ "type": "Point", "coordinates": ["1", 2] }"#));
+        assert!(invalid(r#"{ "type": "MultiPoint", "coordinates": [[0, 0], [1]] }"#));
+        assert!(invalid(r#"{ "type": "FeatureCollection" }"#));
+        assert!(invalid(r#"{ "type": "FeatureCollection", "features": [{ "type": "Point", "coordinates": [0, 0] }] }"#));
+        assert!(invalid(r#"{ "type": "Feature", "geometry": { "type": "Point", "coordinates": [0, 0] }, "properties": 3 }"#));
+    }
+
+    #[test]
+    fn cells() {
+        // The last site's cell is nowhere near the region
+        let sites = [Point::new(1.0, 1.0), Point::new(3.0, 1.0), Point::new(2.0, 3.0), Point::new(100.0, 100.0)];
+        let diagram = VoronoiDiagram::new(&sites).unwrap();
+        let region = ConvexPolygon::rectangle(0.0, 0.0, 4.0, 4.0).unwrap();
+        let properties: Vec<Properties> = (0..sites.len())
+            .map(|i| {
+                let mut properties = Properties::new();
+                properties.insert("site".to_string(), json!(i));
+                properties
+            })
+            .collect();
+
+        let geojson = cells_to_geojson(&diagram, &region, &properties);
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(geojson["type"], "FeatureCollection");
+        assert_eq!(features.len(), sites.len());
+
+        let mut total = 0.0;
+        for (i, feature) in features.iter().enumerate() {
+            assert_eq!(feature["properties"]["site"], json!(i));
+            if i == 3 {
+                assert!(feature["geometry"].is_null());
+                continue;
+            }
+
+            assert_eq!(feature["geometry"]["type"], "Polygon");
+            let ring = points(&feature["geometry"]["coordinates"][0]);
+            assert_eq!(ring.first(), ring.last());
+            assert!(area(&ring[..ring.len() - 1]) > 0.0);
+            total += area(&ring[..ring.len() - 1]);
+        }
+        assert!((total - 16.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn triangles_and_edges() {
+        let sites = [Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 3.0), Point::new(0.0, 3.5), Point::new(2.0, 1.5)];
+        let triangulation = VoronoiDiagram::new(&sites).unwrap().triangulation;
+        let mesh = &triangulation.mesh;
+
+        let triangles = triangles_to_geojson(&triangulation);
+        let triangles = triangles["features"].as_array().unwrap();
+        assert_eq!(triangles.len(), mesh.face_count());
+        for triangle in triangles {
+            let ring = points(&triangle["geometry"]["coordinates"][0]);
+            assert_eq!(ring.len(), 4);
+            assert!(area(&ring[..3]) > 0.0);
+            assert_eq!(triangle["properties"]["sites"].as_array().unwrap().len(), 3);
+        }
+
+        // Every edge once, hull edges included
+        let edges = edges_to_geojson(&triangulation);
+        let written: Vec<(u64, u64)> = edges["features"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|edge| {
+                let ends = &edge["properties"]["sites"];
+                (ends[0].as_u64().unwrap(), ends[1].as_u64().unwrap())
+            })
+            .collect();
+        let unique: HashSet<(u64, u64)> = written.iter().map(|&(a, b)| (a.min(b), a.max(b))).collect();
+        assert_eq!(written.len(), mesh.edge_count() / 2);
+        assert_eq!(unique.len(), written.len());
+
+        for edge in mesh.edges().filter(|&edge| mesh.is_boundary_edge(edge)) {
+            let (a, b) = (mesh.origin(edge).0 as u64, mesh.destination(edge).0 as u64);
+            assert!(unique.contains(&(a.min(b), a.max(b))));
+        }
+    }
+}
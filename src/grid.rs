@@ -0,0 +1,79 @@
+//!
+//! A uniform grid of buckets over a set of sites, for finding the sites near a point without
+//! looking at all of them.
+//!
+
+use crate::point::Point;
+
+/// Buckets the sites into a uniform grid, for visiting them in order of distance
+pub(crate) struct Grid {
+    x_min: f64,
+    y_min: f64,
+    /// The side length of each bucket
+    pub size: f64,
+    columns: usize,
+    rows: usize,
+    buckets: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new(sites: &[Point]) -> Grid {
+        let (mut x_min, mut y_min) = (f64::INFINITY, f64::INFINITY);
+        let (mut x_max, mut y_max) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for site in sites {
+            x_min = x_min.min(site.x);
+            y_min = y_min.min(site.y);
+            x_max = x_max.max(site.x);
+            y_max = y_max.max(site.y);
+        }
+
+        // Around one site per bucket
+        let extent = (x_max - x_min).max(y_max - y_min);
+        let side = (sites.len() as f64).sqrt().ceil().max(1.0);
+        let size = if extent > 0.0 { extent / side } else { 1.0 };
+        let columns = ((x_max - x_min) / size) as usize + 1;
+        let rows = ((y_max - y_min) / size) as usize + 1;
+
+        let mut grid = Grid {
+            x_min,
+            y_min,
+            size,
+            columns,
+            rows,
+            buckets: vec![Vec::new(); columns * rows],
+        };
+        for (i, site) in sites.iter().enumerate() {
+            let (column, row) = grid.bucket(*site);
+            grid.buckets[row * columns + column].push(i);
+        }
+
+        grid
+    }
+
+    fn bucket(&self, p: Point) -> (usize, usize) {
+        let column = ((p.x - self.x_min) / self.size) as usize;
+        let row = ((p.y - self.y_min) / self.size) as usize;
+
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+
+    /// Returns true if any bucket is this many buckets away from p
+    pub fn has_ring(&self, p: Point, ring: usize) -> bool {
+        let (column, row) = self.bucket(p);
+        let furthest = column.max(self.columns - 1 - column).max(row).max(self.rows - 1 - row);
+
+        ring <= furthest
+    }
+
+    /// The sites in buckets exactly this many buckets away from p (in either direction)
+    pub fn ring(&self, p: Point, ring: usize) -> impl Iterator<Item = usize> + '_ {
+        let (column, row) = self.bucket(p);
+        let (column, row, ring) = (column as isize, row as isize, ring as isize);
+
+        (row - ring..=row + ring)
+            .flat_map(move |j| (column - ring..=column + ring).map(move |i| (i, j)))
+            .filter(move |&(i, j)| (i - column).abs() == ring || (j - row).abs() == ring)
+            .filter(move |&(i, j)| i >= 0 && j >= 0 && (i as usize) < self.columns && (j as usize) < self.rows)
+            .flat_map(move |(i, j)| self.buckets[j as usize * self.columns + i as usize].iter().copied())
+    }
+}
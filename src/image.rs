@@ -0,0 +1,473 @@
+//!
+//! A small raster renderer that runs anywhere, for making images of diagrams without a browser
+//! (reference images in tests, for example). It draws anti-aliased lines, circles and filled
+//! polygons into an RGBA buffer, and writes it out as PNG or PPM.
+//!
+//! Pixels are laid out like a canvas: pixel (x, y) is the square from (x, y) to (x + 1, y + 1), so
+//! its center is at (x + 0.5, y + 0.5), and y points down. Edges are smoothed by working out how
+//! much of each pixel a shape covers, and blending the color in by that much.
+//!
+//! `ImageRenderer` draws into an image through the `Renderer` trait, so anything that can be drawn
+//! on a canvas can be drawn here too.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use std::f64::consts::PI;
+use std::io::{self, Write};
+use crate::error::VoronoiError;
+use crate::palette::parse_color;
+use crate::point::Point;
+use crate::polygon::ConvexPolygon;
+use crate::render::{self, dashes, DiagramStyles, Renderer, Style};
+use crate::voronoi::VoronoiDiagram;
+
+/// How many rows of samples are taken through each row of pixels when filling a polygon
+const SUBSAMPLES: usize = 4;
+
+/// An RGBA image, with 8 bits per channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+
+    /// Red, green, blue and alpha for each pixel, row by row (the layout of a canvas's ImageData).
+    /// Colors are not premultiplied by alpha.
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// A transparent image
+    pub fn new(width: u32, height: u32) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Sets every pixel to the color
+    pub fn clear(&mut self, color: [u8; 4]) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+    }
+
+    /// The color of a pixel, or None if it's outside the image
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        Some([self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]])
+    }
+
+    /// Draws a line `width` wide from a to b, with round ends
+    pub fn line(&mut self, a: Point, b: Point, width: f64, color: [u8; 4]) {
+        let half = width / 2.0;
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let length_squared = dx * dx + dy * dy;
+
+        self.cover(a.x.min(b.x) - half, a.y.min(b.y) - half, a.x.max(b.x) + half, a.y.max(b.y) + half, color, |p| {
+            // The distance from the pixel's center to the nearest point on the line
+            let t = if length_squared > 0.0 {
+                (((p.x - a.x) * dx + (p.y - a.y) * dy) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let d = (p.x - a.x - t * dx).hypot(p.y - a.y - t * dy);
+
+            half - d + 0.5
+        });
+    }
+
+    /// Draws a chain of lines through the points. Pass the first point again at the end to close
+    /// it. Each line is drawn on its own, so a translucent color is darker where they meet.
+    pub fn polyline(&mut self, points: &[Point], width: f64, color: [u8; 4]) {
+        for segment in points.windows(2) {
+            self.line(segment[0], segment[1], width, color);
+        }
+    }
+
+    /// Draws the outline of a circle, with a line `width` wide
+    pub fn circle(&mut self, center: Point, radius: f64, width: f64, color: [u8; 4]) {
+        let reach = radius + width / 2.0;
+        self.cover(center.x - reach, center.y - reach, center.x + reach, center.y + reach, color, |p| {
+            width / 2.0 - ((p.x - center.x).hypot(p.y - center.y) - radius).abs() + 0.5
+        });
+    }
+
+    /// Fills a circle
+    pub fn fill_circle(&mut self, center: Point, radius: f64, color: [u8; 4]) {
+        self.cover(center.x - radius, center.y - radius, center.x + radius, center.y + radius, color, |p| {
+            radius - (p.x - center.x).hypot(p.y - center.y) + 0.5
+        });
+    }
+
+    /// Fills a polygon, which doesn't need to be convex. Parts wound around an odd number of times
+    /// are filled (the canvas's "evenodd" rule).
+    pub fn fill_polygon(&mut self, polygon: &[Point], color: [u8; 4]) {
+        let n = polygon.len();
+        if n < 3 || self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        let y_min = polygon.iter().map(|p| p.y).fold(f64::INFINITY, f64::min).floor().max(0.0) as u32;
+        let y_max = polygon.iter().map(|p| p.y).fold(f64::NEG_INFINITY, f64::max).ceil().min(self.height as f64) as u32;
+        let mut coverage = vec![0.0; self.width as usize];
+        let mut crossings = Vec::new();
+
+        for y in y_min..y_max {
+            coverage.iter_mut().for_each(|c| *c = 0.0);
+
+            // Each row of samples adds the stretches of the row inside the polygon, exactly along
+            // x, so only y needs more than one sample
+            for sample in 0..SUBSAMPLES {
+                let sy = y as f64 + (sample as f64 + 0.5) / SUBSAMPLES as f64;
+
+                crossings.clear();
+                for i in 0..n {
+                    let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+                    if (a.y > sy) != (b.y > sy) {
+                        crossings.push(a.x + (sy - a.y) * (b.x - a.x) / (b.y - a.y));
+                    }
+                }
+                crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+                for span in crossings.chunks_exact(2) {
+                    let (from, to) = (span[0].max(0.0), span[1].min(self.width as f64));
+                    let mut x = from.floor() as usize;
+                    while (x as f64) < to {
+                        let overlap = to.min(x as f64 + 1.0) - from.max(x as f64);
+                        coverage[x] += overlap / SUBSAMPLES as f64;
+                        x += 1;
+                    }
+                }
+            }
+
+            for (x, &c) in coverage.iter().enumerate() {
+                if c > 0.0 {
+                    self.blend(x as u32, y, color, c);
+                }
+            }
+        }
+    }
+
+    /// Writes the image as a PNG, with transparency
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Writes the image as a binary PPM, which has no transparency, so the image is drawn over
+    /// white first
+    pub fn write_ppm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+
+        let mut rgb = Vec::with_capacity(self.pixels.len() / 4 * 3);
+        for pixel in self.pixels.chunks_exact(4) {
+            let alpha = pixel[3] as f64 / 255.0;
+            for &channel in &pixel[..3] {
+                rgb.push((channel as f64 * alpha + 255.0 * (1.0 - alpha)).round() as u8);
+            }
+        }
+
+        writer.write_all(&rgb)
+    }
+
+    /// Blends the color into every pixel in a box by its coverage, given by a function of the
+    /// pixel's center that is 1 or more for pixels inside the shape and 0 or less outside
+    fn cover<F: Fn(Point) -> f64>(&mut self, x_min: f64, y_min: f64, x_max: f64, y_max: f64, color: [u8; 4], coverage: F) {
+        let clamp_x = |x: f64| x.clamp(0.0, self.width as f64) as u32;
+        let clamp_y = |y: f64| y.clamp(0.0, self.height as f64) as u32;
+        let (x_from, x_to) = (clamp_x(x_min.floor() - 1.0), clamp_x(x_max.ceil() + 1.0));
+        let (y_from, y_to) = (clamp_y(y_min.floor() - 1.0), clamp_y(y_max.ceil() + 1.0));
+
+        for y in y_from..y_to {
+            for x in x_from..x_to {
+                let c = coverage(Point::new(x as f64 + 0.5, y as f64 + 0.5)).min(1.0);
+                if c > 0.0 {
+                    self.blend(x, y, color, c);
+                }
+            }
+        }
+    }
+
+    /// Draws the color over a pixel, with its alpha scaled by the coverage
+    fn blend(&mut self, x: u32, y: u32, color: [u8; 4], coverage: f64) {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        let source = color[3] as f64 / 255.0 * coverage.min(1.0);
+        let destination = self.pixels[i + 3] as f64 / 255.0;
+
+        let alpha = source + destination * (1.0 - source);
+        if alpha <= 0.0 {
+            return;
+        }
+
+        for (pixel, &channel) in self.pixels[i..i + 3].iter_mut().zip(color.iter()) {
+            let mixed = (channel as f64 * source + *pixel as f64 * destination * (1.0 - source)) / alpha;
+            *pixel = mixed.round() as u8;
+        }
+        self.pixels[i + 3] = (alpha * 255.0).round() as u8;
+    }
+}
+
+/// Draws into an image. Colors are read with `parse_color`, and a style with a color it can't read
+/// fails when it is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageRenderer {
+    pub image: Image,
+
+    /// The current style's stroke and fill, with the opacity applied
+    stroke: Option<[u8; 4]>,
+    fill: Option<[u8; 4]>,
+    style: Style,
+}
+
+impl ImageRenderer {
+    /// A renderer for a transparent image, starting out drawing thin black lines
+    pub fn new(width: u32, height: u32) -> ImageRenderer {
+        ImageRenderer {
+            image: Image::new(width, height),
+            stroke: Some([0, 0, 0, 255]),
+            fill: None,
+            style: Style::stroke("#000000", 1.0),
+        }
+    }
+
+    /// Strokes a line through the points, in dashes if the style has them
+    fn outline(&mut self, points: &[Point]) {
+        let color = match self.stroke {
+            Some(color) if self.style.stroke_width > 0.0 => color,
+            _ => return,
+        };
+
+        for piece in dashes(points, &self.style.dash) {
+            self.image.polyline(&piece, self.style.stroke_width, color);
+        }
+    }
+}
+
+impl Renderer for ImageRenderer {
+    fn clear(&mut self) -> Result<(), VoronoiError> {
+        self.image.clear([0, 0, 0, 0]);
+        Ok(())
+    }
+
+    fn style(&mut self, style: &Style) -> Result<(), VoronoiError> {
+        let color = |color: &Option<String>| -> Result<Option<[u8; 4]>, VoronoiError> {
+            match color {
+                Some(color) => {
+                    let mut rgba = parse_color(color).ok_or_else(|| VoronoiError::Render(format!("Unknown color {}", color)))?;
+                    rgba[3] = (rgba[3] as f64 * style.opacity.clamp(0.0, 1.0)).round() as u8;
+                    Ok(Some(rgba))
+                }
+                None => Ok(None),
+            }
+        };
+
+        self.stroke = color(&style.stroke)?;
+        self.fill = color(&style.fill)?;
+        self.style = style.clone();
+        Ok(())
+    }
+
+    fn circle(&mut self, center: Point, radius: f64) -> Result<(), VoronoiError> {
+        if let Some(fill) = self.fill {
+            self.image.fill_circle(center, radius, fill);
+        }
+
+        if self.style.dash.is_empty() {
+            if let Some(stroke) = self.stroke {
+                self.image.circle(center, radius, self.style.stroke_width, stroke);
+            }
+        } else {
+            // Dashes are laid along the circle as a polygon, with sides about a pixel long
+            let sides = ((2.0 * PI * radius).ceil() as usize).clamp(16, 4096);
+            let outline: Vec<Point> = (0..=sides)
+                .map(|i| {
+                    let angle = 2.0 * PI * i as f64 / sides as f64;
+                    Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin())
+                })
+                .collect();
+            self.outline(&outline);
+        }
+
+        Ok(())
+    }
+
+    fn polyline(&mut self, points: &[Point]) -> Result<(), VoronoiError> {
+        self.outline(points);
+        Ok(())
+    }
+
+    fn polygon(&mut self, points: &[Point]) -> Result<(), VoronoiError> {
+        if let Some(fill) = self.fill {
+            self.image.fill_polygon(points, fill);
+        }
+
+        if let Some(&first) = points.first() {
+            let mut outline = points.to_vec();
+            outline.push(first);
+            self.outline(&outline);
+        }
+
+        Ok(())
+    }
+
+    fn image(&mut self, corner: Point, image: &Image) -> Result<(), VoronoiError> {
+        let (left, top) = (corner.x.round() as i64, corner.y.round() as i64);
+
+        for y in 0..image.height as i64 {
+            for x in 0..image.width as i64 {
+                let (to_x, to_y) = (left + x, top + y);
+                if to_x < 0 || to_y < 0 || to_x >= self.image.width as i64 || to_y >= self.image.height as i64 {
+                    continue;
+                }
+
+                let from = (y as usize * image.width as usize + x as usize) * 4;
+                let to = (to_y as usize * self.image.width as usize + to_x as usize) * 4;
+                self.image.pixels[to..to + 4].copy_from_slice(&image.pixels[from..from + 4]);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Draws a Voronoi diagram the way the canvas demos do (see `DiagramStyles::default`), with cells
+/// clipped to the image. Fails if the image has no area.
+pub fn diagram_image(diagram: &VoronoiDiagram, width: u32, height: u32) -> Result<Image, VoronoiError> {
+    let region = ConvexPolygon::rectangle(0.0, 0.0, width.into(), height.into())?;
+    let mut renderer = ImageRenderer::new(width, height);
+    render::diagram(&mut renderer, diagram, &region, &DiagramStyles::default())?;
+
+    Ok(renderer.image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    fn alpha(image: &Image, x: u32, y: u32) -> u8 {
+        image.pixel(x, y).unwrap()[3]
+    }
+
+    /// Every pixel a different color, with some transparency
+    fn gradient() -> Image {
+        let mut image = Image::new(7, 5);
+        for (i, pixel) in image.pixels.chunks_exact_mut(4).enumerate() {
+            pixel.copy_from_slice(&[(i * 7) as u8, (255 - i * 5) as u8, (i * 3) as u8, (i * 40 % 256) as u8]);
+        }
+
+        image
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let image = gradient();
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+
+        let mut reader = png::Decoder::new(&png[..]).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+
+        assert_eq!((info.width, info.height), (7, 5));
+        assert_eq!((info.color_type, info.bit_depth), (png::ColorType::Rgba, png::BitDepth::Eight));
+        assert_eq!(&pixels[..info.buffer_size()], &image.pixels[..]);
+    }
+
+    #[test]
+    fn ppm() {
+        let mut image = gradient();
+        image.pixels[..4].copy_from_slice(&RED);
+        image.pixels[4..8].copy_from_slice(&CLEAR);
+        image.pixels[8..12].copy_from_slice(&[0, 0, 0, 128]);
+
+        let mut ppm = Vec::new();
+        image.write_ppm(&mut ppm).unwrap();
+
+        let header = b"P6\n7 5\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + 7 * 5 * 3);
+
+        // Drawn over white
+        let rgb = &ppm[header.len()..];
+        assert_eq!(&rgb[..3], &[255, 0, 0]);
+        assert_eq!(&rgb[3..6], &[255, 255, 255]);
+        assert_eq!(&rgb[6..9], &[127, 127, 127]);
+    }
+
+    #[test]
+    fn line() {
+        let mut image = Image::new(20, 20);
+        image.line(Point::new(2.0, 10.5), Point::new(18.0, 10.5), 3.0, RED);
+
+        assert_eq!(image.pixel(10, 10), Some(RED));
+        assert_eq!(image.pixel(10, 9), Some(RED));
+        assert_eq!(image.pixel(10, 11), Some(RED));
+        assert_eq!(image.pixel(10, 5), Some(CLEAR));
+        assert_eq!(image.pixel(10, 13), Some(CLEAR));
+
+        // The round end covers half of the pixel whose center is 1.5 away from it
+        assert_eq!(alpha(&image, 0, 10), 128);
+        assert_eq!(image.pixel(0, 8), Some(CLEAR));
+    }
+
+    #[test]
+    fn circle() {
+        let center = Point::new(10.5, 10.5);
+        let mut image = Image::new(21, 21);
+        image.circle(center, 6.0, 2.0, RED);
+
+        // Pixel centers right on the circle, and well inside and outside it
+        for &(x, y) in [(10, 4), (16, 10), (10, 16), (4, 10)].iter() {
+            assert_eq!(image.pixel(x, y), Some(RED));
+        }
+        assert_eq!(image.pixel(10, 10), Some(CLEAR));
+        assert_eq!(image.pixel(0, 0), Some(CLEAR));
+
+        let mut image = Image::new(21, 21);
+        image.fill_circle(center, 6.0, RED);
+        assert_eq!(image.pixel(10, 10), Some(RED));
+        assert_eq!(image.pixel(13, 13), Some(RED));
+        assert_eq!(image.pixel(18, 18), Some(CLEAR));
+    }
+
+    #[test]
+    fn filled_polygon() {
+        let mut image = Image::new(20, 20);
+        image.fill_polygon(&[Point::new(2.0, 2.0), Point::new(18.0, 2.0), Point::new(2.0, 18.0)], RED);
+
+        assert_eq!(image.pixel(4, 4), Some(RED));
+        assert_eq!(image.pixel(9, 9), Some(RED));
+        assert_eq!(image.pixel(1, 4), Some(CLEAR));
+        assert_eq!(image.pixel(16, 16), Some(CLEAR));
+
+        // The hypotenuse cuts this pixel in half
+        let half = alpha(&image, 9, 10);
+        assert!((120..=136).contains(&half), "alpha {}", half);
+        assert_eq!(&image.pixel(9, 10).unwrap()[..3], &RED[..3]);
+    }
+
+    #[test]
+    fn translucent_colors_blend() {
+        let mut image = Image::new(4, 4);
+        image.clear([0, 0, 255, 255]);
+        image.fill_polygon(&[Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 4.0), Point::new(0.0, 4.0)], [255, 0, 0, 128]);
+
+        assert_eq!(image.pixel(1, 1), Some([128, 0, 127, 255]));
+    }
+}
@@ -0,0 +1,216 @@
+//!
+//! The Jump Flooding Algorithm: a fast, approximate way to label every pixel of a raster with its
+//! nearest site. Each site starts at its own pixel, then every pixel looks at the pixels a step
+//! away in all eight directions and takes the nearest site any of them knows about. The step
+//! starts at half the raster's size and halves each pass, so there are only log2(size) passes,
+//! and each pass does the same small amount of work for every pixel, however many sites there are.
+//!
+//! A site can get lost on the way to a pixel it should own, so a few pixels end up with the wrong
+//! label (usually near where three cells meet). Two common fixes trade some speed for accuracy:
+//! 1+JFA does an extra pass with a step of 1 first, and JFA+2 does extra passes with steps of 2
+//! and 1 at the end. Use `RasterVoronoi::error_rate` to see how close the result is.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::metric::Metric;
+use crate::point::Point;
+use crate::raster::{RasterVoronoi, NO_SITE};
+
+/// Which passes to make
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// Steps of size/2, size/4, ..., 1
+    Jfa,
+
+    /// An extra pass with a step of 1 before the usual passes
+    OnePlusJfa,
+
+    /// Extra passes with steps of 2 and 1 after the usual passes
+    JfaPlusTwo,
+}
+
+impl Variant {
+    /// The step of every pass, in order, for a raster whose larger side is `size`
+    fn steps(self, size: u32) -> Vec<u32> {
+        let mut steps = Vec::new();
+        if self == Variant::OnePlusJfa {
+            steps.push(1);
+        }
+
+        let mut step = size.next_power_of_two() / 2;
+        while step >= 1 {
+            steps.push(step);
+            step /= 2;
+        }
+
+        if self == Variant::JfaPlusTwo {
+            steps.extend_from_slice(&[2, 1]);
+        }
+
+        steps
+    }
+}
+
+/// Labels a `width` by `height` raster with the (approximately) nearest site to each pixel, and
+/// keeps the distance to it. Pixels are measured the same way as `RasterVoronoi::new`, so the two
+/// can be compared directly.
+///
+/// Each site starts at the pixel it is closest to; a site outside the raster starts at the nearest
+/// pixel on its edge. When two sites start at the same pixel, the one nearer to it wins (or the
+/// first, if they are equally near), and the other can't reach any pixel at all, so sites should
+/// be at least a pixel apart.
+pub fn jump_flooding<M: Metric + ?Sized>(width: u32, height: u32, sites: &[Point], metric: &M, variant: Variant) -> RasterVoronoi {
+    let pixels = width as usize * height as usize;
+    let mut labels = vec![NO_SITE; pixels];
+    let mut distances = vec![f64::INFINITY; pixels];
+
+    if pixels == 0 {
+        return RasterVoronoi {
+            width,
+            height,
+            labels,
+            distances: Some(Vec::new()),
+        };
+    }
+
+    let index = |x: u32, y: u32| y as usize * width as usize + x as usize;
+    let pixel = |x: u32, y: u32| Point::new(x.into(), y.into());
+
+    // Seed each site at its own pixel
+    for (i, &site) in sites.iter().enumerate() {
+        if !site.x.is_finite() || !site.y.is_finite() {
+            continue;
+        }
+
+        let x = site.x.round().max(0.0).min((width - 1) as f64) as u32;
+        let y = site.y.round().max(0.0).min((height - 1) as f64) as u32;
+        let d = metric.distance(site, pixel(x, y));
+
+        if d < distances[index(x, y)] {
+            labels[index(x, y)] = i as u32;
+            distances[index(x, y)] = d;
+        }
+    }
+
+    let mut next_labels = labels.clone();
+    let mut next_distances = distances.clone();
+
+    for step in variant.steps(width.max(height)) {
+        for y in 0..height {
+            for x in 0..width {
+                let p = pixel(x, y);
+                let mut closest = labels[index(x, y)];
+                let mut closest_distance = distances[index(x, y)];
+
+                for dy in [-1i64, 0, 1] {
+                    for dx in [-1i64, 0, 1] {
+                        let (nx, ny) = (x as i64 + dx * step as i64, y as i64 + dy * step as i64);
+                        if (dx == 0 && dy == 0) || nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                            continue;
+                        }
+
+                        let label = labels[index(nx as u32, ny as u32)];
+                        if label == NO_SITE || label == closest {
+                            continue;
+                        }
+
+                        // Ties go to the site that comes first, like the exact labels
+                        let d = metric.distance(sites[label as usize], p);
+                        if d < closest_distance || (d == closest_distance && label < closest) {
+                            closest = label;
+                            closest_distance = d;
+                        }
+                    }
+                }
+
+                next_labels[index(x, y)] = closest;
+                next_distances[index(x, y)] = closest_distance;
+            }
+        }
+
+        std::mem::swap(&mut labels, &mut next_labels);
+        std::mem::swap(&mut distances, &mut next_distances);
+    }
+
+    // Pixels no site reached (only possible with no sites) have no distance either
+    for (distance, &label) in distances.iter_mut().zip(labels.iter()) {
+        if label == NO_SITE {
+            *distance = f64::NAN;
+        }
+    }
+
+    RasterVoronoi {
+        width,
+        height,
+        labels,
+        distances: Some(distances),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{seeded_rng, uniform};
+    use crate::metric::Euclidean;
+
+    const WIDTH: u32 = 128;
+    const HEIGHT: u32 = 96;
+
+    fn sites() -> Vec<Point> {
+        uniform(&mut seeded_rng(3), 60, 0.0..WIDTH as f64, 0.0..HEIGHT as f64)
+    }
+
+    fn exact(sites: &[Point]) -> RasterVoronoi {
+        RasterVoronoi::with_distances(WIDTH, HEIGHT, sites.len(), |i, p| Euclidean.distance(sites[i], p))
+    }
+
+    #[test]
+    fn no_errors_against_itself() {
+        let sites = sites();
+
+        for &variant in [Variant::Jfa, Variant::OnePlusJfa, Variant::JfaPlusTwo].iter() {
+            let flooded = jump_flooding(WIDTH, HEIGHT, &sites, &Euclidean, variant);
+            assert_eq!(flooded.error_rate(&flooded), 0.0);
+        }
+
+        let exact = exact(&sites);
+        assert_eq!(exact.error_rate(&exact), 0.0);
+    }
+
+    #[test]
+    fn extra_passes_are_no_worse() {
+        let sites = sites();
+        let exact = exact(&sites);
+        let error_rate = |variant| jump_flooding(WIDTH, HEIGHT, &sites, &Euclidean, variant).error_rate(&exact);
+
+        // Plain JFA loses a few pixels on this input
+        let jfa = error_rate(Variant::Jfa);
+        assert!(jfa > 0.0 && jfa < 0.01, "{} of pixels are wrong", jfa);
+        assert!(error_rate(Variant::OnePlusJfa) <= jfa);
+        assert!(error_rate(Variant::JfaPlusTwo) <= jfa);
+    }
+
+    #[test]
+    fn distances_match_labels() {
+        let sites = sites();
+
+        for &variant in [Variant::Jfa, Variant::OnePlusJfa, Variant::JfaPlusTwo].iter() {
+            let flooded = jump_flooding(WIDTH, HEIGHT, &sites, &Euclidean, variant);
+            for y in 0..HEIGHT {
+                for x in 0..WIDTH {
+                    let label = flooded.label_at(x, y).unwrap();
+                    let distance = Euclidean.distance(sites[label], Point::new(x.into(), y.into()));
+                    assert_eq!(flooded.distance_at(x, y), Some(distance));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn no_sites() {
+        let flooded = jump_flooding(4, 3, &[], &Euclidean, Variant::Jfa);
+        assert!(flooded.labels.iter().all(|&label| label == NO_SITE));
+        assert_eq!(flooded.distance_at(0, 0), None);
+    }
+}
@@ -1,18 +1,43 @@
-use point::Metric;
 use point::Point;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
-mod dulaney;
-mod point;
-mod demos;
-use std::panic;
-extern crate console_error_panic_hook;
+pub mod dulaney;
+pub mod error;
+mod grid;
+pub mod mesh;
+pub mod metric;
+pub mod point;
+pub mod polygon;
+pub mod power;
+pub mod predicates;
+pub mod voronoi;
+pub mod fortune;
+pub mod lloyd;
+pub mod rectilinear;
+pub mod apollonius;
+pub mod raster;
+pub mod jfa;
+pub mod distance_transform;
+pub mod worley;
+pub mod generators;
+pub mod poisson_disk;
+pub mod palette;
+pub mod svg;
+pub mod image;
+pub mod render;
+pub mod geojson;
+pub mod triangle_format;
+pub mod demos;
 
+/// Logs to the browser console, with the same arguments as `format!`. Only available with the
+/// `wasm` feature.
+#[cfg(feature = "wasm")]
 #[macro_export]
 macro_rules! console_log {
     // Note that this is using the `log` function imported above during
     // `bare_bones`
     ($($t:tt)*) => {
-        unsafe {
+        {
             use web_sys::console;
             console::log_1(&format_args!($($t)*).to_string().into())
         }
@@ -20,9 +45,10 @@ macro_rules! console_log {
 }
 
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn initialize() {
-    panic::set_hook(Box::new(console_error_panic_hook::hook));
+    std::panic::set_hook(Box::new(console_error_panic_hook::hook));
 }
 
 
@@ -0,0 +1,256 @@
+//!
+//! Lloyd relaxation, which spreads sites out evenly by repeatedly moving each site to the centroid
+//! of its Voronoi cell. The sites converge towards a centroidal Voronoi tessellation, where every
+//! site is the centroid of its own cell.
+//!
+//! With a density function, centroids are weighted by the density, and sites gather where it is
+//! high. Each step never increases the energy of the tessellation, `E = Σ ∫ ρ(x) |x - zᵢ|² dx`,
+//! summed over the cells Vᵢ (each integrated over its own area) with sites zᵢ and density ρ. The
+//! energy is reported for every iteration to show how the relaxation is going.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::error::VoronoiError;
+use crate::metric::Euclidean;
+use crate::point::Point;
+use crate::polygon::{centroid, ConvexPolygon};
+use crate::voronoi::VoronoiDiagram;
+
+/// How many times each side of a triangle is divided when integrating a density function. The
+/// quadrature rule is only exact for quadratics, so a density needs a finer grid.
+const SUBDIVISIONS: usize = 4;
+
+/// When to stop relaxing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stop {
+    /// Run exactly this many iterations
+    Iterations(usize),
+
+    /// Stop once no site moves further than the tolerance in an iteration, or after the maximum
+    /// number of iterations, whichever happens first
+    Tolerance { tolerance: f64, max_iterations: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relaxation {
+    /// The relaxed sites, in the same order as they were given
+    pub sites: Vec<Point>,
+
+    /// The energy at the start of each iteration, before the sites were moved
+    pub energies: Vec<f64>,
+
+    /// True if the sites stopped moving within the tolerance. Always false for Stop::Iterations.
+    pub converged: bool,
+}
+
+/// Relaxes the sites inside a region. The density function, if there is one, must not be negative
+/// anywhere in the region; without one, the density is 1 everywhere. Sites outside the region
+/// have no cell inside it, so they stay where they are.
+///
+/// Fails if the sites can't be triangulated, in the same cases as `dulaney_triangulation`.
+pub fn lloyd_relaxation(
+    sites: &[Point],
+    region: &ConvexPolygon,
+    stop: Stop,
+    density: Option<&dyn Fn(Point) -> f64>,
+) -> Result<Relaxation, VoronoiError> {
+
+    let (tolerance, max_iterations) = match stop {
+        Stop::Iterations(iterations) => (None, iterations),
+        Stop::Tolerance { tolerance, max_iterations } => (Some(tolerance), max_iterations),
+    };
+
+    let mut sites = sites.to_vec();
+    let mut energies = Vec::with_capacity(max_iterations);
+    let mut converged = false;
+
+    for _ in 0..max_iterations {
+        let diagram = VoronoiDiagram::new(&sites)?;
+
+        let mut energy = 0.0;
+        let mut furthest: f64 = 0.0;
+        for (site, cell) in sites.iter_mut().zip(diagram.clip(region)) {
+            if cell.is_empty() {
+                continue;
+            }
+
+            let moments = Moments::of(&cell, *site, density);
+            energy += moments.energy;
+
+            let target = moments.centroid().or_else(|| centroid(&cell)).unwrap_or(*site);
+            furthest = furthest.max(site.dist(&target, &Euclidean));
+            *site = target;
+        }
+
+        energies.push(energy);
+
+        if matches!(tolerance, Some(tolerance) if furthest <= tolerance) {
+            converged = true;
+            break;
+        }
+    }
+
+    Ok(Relaxation {
+        sites,
+        energies,
+        converged,
+    })
+}
+
+/// The integrals over a cell needed for one step: its mass, its first moments, and its energy
+/// about the site
+#[derive(Debug, Default)]
+struct Moments {
+    mass: f64,
+    x: f64,
+    y: f64,
+    energy: f64,
+}
+
+impl Moments {
+    /// Integrates over a convex polygon by splitting it into a fan of triangles, each of which is
+    /// divided into a grid of smaller triangles. Each small triangle is sampled at the midpoints of
+    /// its edges, which is exact for polynomials up to degree 2: the area, centroid and energy
+    /// are all exact when the density is constant.
+    fn of(polygon: &[Point], site: Point, density: Option<&dyn Fn(Point) -> f64>) -> Moments {
+        let k = if density.is_some() { SUBDIVISIONS } else { 1 };
+        let mut moments = Moments::default();
+
+        let origin = polygon[0];
+        for i in 1..polygon.len() - 1 {
+            let (b, c) = (polygon[i], polygon[i + 1]);
+
+            // Steps along two sides of the triangle, one grid cell long
+            let u = ((b.x - origin.x) / k as f64, (b.y - origin.y) / k as f64);
+            let v = ((c.x - origin.x) / k as f64, (c.y - origin.y) / k as f64);
+            let grid = |i: usize, j: usize| {
+                Point::new(
+                    origin.x + u.0 * i as f64 + v.0 * j as f64,
+                    origin.y + u.1 * i as f64 + v.1 * j as f64,
+                )
+            };
+            let area = (u.0 * v.1 - u.1 * v.0) / 2.0;
+
+            for i in 0..k {
+                for j in 0..k - i {
+                    moments.add([grid(i, j), grid(i + 1, j), grid(i, j + 1)], area, site, density);
+
+                    if i + j + 1 < k {
+                        moments.add([grid(i + 1, j), grid(i + 1, j + 1), grid(i, j + 1)], area, site, density);
+                    }
+                }
+            }
+        }
+
+        moments
+    }
+
+    fn add(&mut self, [a, b, c]: [Point; 3], area: f64, site: Point, density: Option<&dyn Fn(Point) -> f64>) {
+        let midpoints = [
+            Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0),
+            Point::new((b.x + c.x) / 2.0, (b.y + c.y) / 2.0),
+            Point::new((c.x + a.x) / 2.0, (c.y + a.y) / 2.0),
+        ];
+
+        for p in midpoints.iter() {
+            let weight = area / 3.0 * density.map_or(1.0, |density| density(*p));
+
+            self.mass += weight;
+            self.x += weight * p.x;
+            self.y += weight * p.y;
+            self.energy += weight * ((p.x - site.x).powi(2) + (p.y - site.y).powi(2));
+        }
+    }
+
+    /// The weighted centroid, or None if the cell has no mass
+    fn centroid(&self) -> Option<Point> {
+        if self.mass > 0.0 {
+            Some(Point::new(self.x / self.mass, self.y / self.mass))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{seeded_rng, uniform};
+
+    fn square() -> ConvexPolygon {
+        ConvexPolygon::rectangle(0.0, 0.0, 100.0, 100.0).unwrap()
+    }
+
+    fn sites(seed: u64) -> Vec<Point> {
+        uniform(&mut seeded_rng(seed), 30, 0.0..100.0, 0.0..100.0)
+    }
+
+    fn assert_decreasing(energies: &[f64]) {
+        for pair in energies.windows(2) {
+            assert!(pair[1] <= pair[0] * (1.0 + 1e-9), "energy went from {} to {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn energy_never_increases() {
+        let density = |p: Point| 1.0 + (p.x / 25.0).sin().powi(2);
+
+        for seed in 0..5 {
+            let relaxation = lloyd_relaxation(&sites(seed), &square(), Stop::Iterations(20), None).unwrap();
+            assert_eq!(relaxation.energies.len(), 20);
+            assert!(!relaxation.converged);
+            assert_decreasing(&relaxation.energies);
+
+            let relaxation = lloyd_relaxation(&sites(seed), &square(), Stop::Iterations(20), Some(&density)).unwrap();
+            assert_decreasing(&relaxation.energies);
+        }
+    }
+
+    #[test]
+    fn stops_within_tolerance() {
+        let stop = Stop::Tolerance { tolerance: 1e-3, max_iterations: 1000 };
+        let relaxation = lloyd_relaxation(&sites(1), &square(), stop, None).unwrap();
+
+        assert!(relaxation.converged);
+        assert!(relaxation.energies.len() < 1000);
+
+        // One more step barely moves anything
+        let next = lloyd_relaxation(&relaxation.sites, &square(), Stop::Iterations(1), None).unwrap();
+        for (a, b) in relaxation.sites.iter().zip(next.sites.iter()) {
+            assert!(a.dist(b, &Euclidean) <= 1e-3);
+        }
+
+        // Too few iterations to get there
+        let stop = Stop::Tolerance { tolerance: 1e-3, max_iterations: 2 };
+        let relaxation = lloyd_relaxation(&sites(1), &square(), stop, None).unwrap();
+        assert!(!relaxation.converged);
+        assert_eq!(relaxation.energies.len(), 2);
+    }
+
+    #[test]
+    fn constant_density() {
+        let unweighted = lloyd_relaxation(&sites(2), &square(), Stop::Iterations(10), None).unwrap();
+        let weighted = lloyd_relaxation(&sites(2), &square(), Stop::Iterations(10), Some(&|_| 3.0)).unwrap();
+
+        for (a, b) in unweighted.sites.iter().zip(weighted.sites.iter()) {
+            assert!(a.dist(b, &Euclidean) < 1e-9);
+        }
+        for (a, b) in unweighted.energies.iter().zip(weighted.energies.iter()) {
+            assert!((3.0 * a - b).abs() < 1e-9 * b);
+        }
+    }
+
+    #[test]
+    fn sites_gather_where_the_density_is_high() {
+        let density = |p: Point| if p.x < 50.0 { 10.0 } else { 1.0 };
+        let left = |sites: &[Point]| sites.iter().filter(|p| p.x < 50.0).count();
+
+        for seed in 0..5 {
+            let unweighted = lloyd_relaxation(&sites(seed), &square(), Stop::Iterations(30), None).unwrap();
+            let weighted = lloyd_relaxation(&sites(seed), &square(), Stop::Iterations(30), Some(&density)).unwrap();
+
+            assert!(left(&weighted.sites) > left(&unweighted.sites) + 3);
+        }
+    }
+}
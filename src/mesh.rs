@@ -0,0 +1,546 @@
+//!
+//! A half-edge mesh (also called a doubly connected edge list, or DCEL) of polygons in the plane.
+//!
+//! Every edge between two vertices is split into two half-edges pointing in opposite directions,
+//! called twins. Each half-edge knows the vertex it starts from, the face on its left, and the next
+//! and previous half-edges around that face. This makes every adjacency query (the faces around a
+//! vertex, the neighbors of a face, and so on) a matter of following a few indices.
+//!
+//! Faces run counter-clockwise (in a y-up coordinate system). Edges on the outside of the mesh
+//! still have twins: they belong to no face, and are linked into loops around the boundary.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::error::VoronoiError;
+use crate::point::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VertexId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EdgeId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FaceId(pub usize);
+
+#[derive(Debug, Clone, PartialEq)]
+struct Vertex {
+    point: Point,
+
+    /// An outgoing half-edge. For vertices on the boundary, this is the outgoing boundary edge.
+    edge: Option<EdgeId>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct HalfEdge {
+    origin: VertexId,
+    twin: EdgeId,
+    next: EdgeId,
+    prev: EdgeId,
+    face: Option<FaceId>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Face {
+    edge: EdgeId,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh {
+    vertices: Vec<Vertex>,
+    edges: Vec<HalfEdge>,
+    faces: Vec<Face>,
+}
+
+impl Mesh {
+    /// Builds a mesh from its vertices and faces. Each face lists the indices of its vertices in
+    /// counter-clockwise order, and face i of the mesh is faces[i].
+    ///
+    /// Fails if a face has fewer than 3 vertices or refers to a vertex that doesn't exist, or if
+    /// two faces share an edge in the same direction (which means they overlap, or one of them is
+    /// clockwise). Also fails if the faces around a vertex don't form a single fan, like two
+    /// triangles that only touch at a corner, since walking around that vertex couldn't reach
+    /// all of its edges.
+    pub fn from_faces<F: AsRef<[usize]>>(points: &[Point], faces: &[F]) -> Result<Mesh, VoronoiError> {
+        // Room for both halves of every edge, which is plenty
+        let sides: usize = faces.iter().map(|face| face.as_ref().len()).sum();
+
+        let mut mesh = Mesh {
+            vertices: points.iter().map(|&point| Vertex { point, edge: None }).collect(),
+            edges: Vec::with_capacity(2 * sides),
+            faces: Vec::with_capacity(faces.len()),
+        };
+
+        for (f, face) in faces.iter().enumerate() {
+            let face = face.as_ref();
+            if face.len() < 3 {
+                return Err(VoronoiError::InvalidMesh(format!("face {} has fewer than 3 vertices", f)));
+            }
+            if let Some(&v) = face.iter().find(|&&v| v >= points.len()) {
+                return Err(VoronoiError::InvalidMesh(format!("face {} refers to missing vertex {}", f, v)));
+            }
+
+            let first = mesh.edges.len();
+            let n = face.len();
+            for (i, &origin) in face.iter().enumerate() {
+                let edge = EdgeId(first + i);
+
+                mesh.edges.push(HalfEdge {
+                    origin: VertexId(origin),
+                    twin: edge,
+                    next: EdgeId(first + (i + 1) % n),
+                    prev: EdgeId(first + (i + n - 1) % n),
+                    face: Some(FaceId(f)),
+                });
+                mesh.vertices[origin].edge = Some(edge);
+            }
+
+            mesh.faces.push(Face { edge: EdgeId(first) });
+        }
+
+        // Group the half-edges by the vertex they start from, along with where they end, so
+        // finding the twin of u -> v only means searching the few edges leaving v
+        let interior = mesh.edges.len();
+        let destination = |e: usize| mesh.edges[mesh.edges[e].next.0].origin;
+
+        let mut start = vec![0; points.len() + 1];
+        for edge in mesh.edges.iter() {
+            start[edge.origin.0 + 1] += 1;
+        }
+        for v in 0..points.len() {
+            start[v + 1] += start[v];
+        }
+        let mut outgoing = vec![(VertexId(0), EdgeId(0)); interior];
+        let mut fill = start.clone();
+        for (e, edge) in mesh.edges.iter().enumerate() {
+            outgoing[fill[edge.origin.0]] = (destination(e), EdgeId(e));
+            fill[edge.origin.0] += 1;
+        }
+        let leaving = |v: VertexId| &outgoing[start[v.0]..start[v.0 + 1]];
+
+        // Pair up the twins. Edges without one are on the boundary, and get a twin with no face.
+        let mut boundary = Vec::new();
+        for origin in mesh.vertices() {
+            for (i, &(destination, e)) in leaving(origin).iter().enumerate() {
+                if leaving(origin)[..i].iter().any(|&(end, _)| end == destination) {
+                    return Err(VoronoiError::InvalidMesh(format!(
+                        "edge {} -> {} is used twice in the same direction", origin.0, destination.0
+                    )));
+                }
+
+                match leaving(destination).iter().find(|&&(end, _)| end == origin) {
+                    Some(&(_, twin)) => mesh.edges[e.0].twin = twin,
+                    None => {
+                        let twin = EdgeId(mesh.edges.len());
+                        mesh.edges[e.0].twin = twin;
+                        mesh.edges.push(HalfEdge {
+                            origin: destination,
+                            twin: e,
+                            next: twin,
+                            prev: twin,
+                            face: None,
+                        });
+                        boundary.push(twin);
+                    }
+                }
+            }
+        }
+
+        // Link the boundary edges into loops. The boundary edge after b (which ends at v) is the
+        // first boundary edge found by turning counter-clockwise around v, starting from b's twin.
+        for &b in boundary.iter() {
+            let mut e = mesh.edges[b.0].twin;
+            while mesh.edges[e.0].face.is_some() {
+                e = mesh.edges[mesh.edges[e.0].prev.0].twin;
+            }
+
+            mesh.edges[b.0].next = e;
+            mesh.edges[e.0].prev = b;
+        }
+
+        // Boundary vertices start from their boundary edge, so walking around them begins (and
+        // ends) on the outside of the mesh
+        for &b in boundary.iter() {
+            let origin = mesh.edges[b.0].origin;
+            mesh.vertices[origin.0].edge = Some(b);
+        }
+
+        // A vertex joining more than one fan of faces is pinched: walking around it from its edge
+        // only visits one of them
+        let mut degree = vec![0; points.len()];
+        for edge in mesh.edges.iter() {
+            degree[edge.origin.0] += 1;
+        }
+        if let Some(vertex) = mesh.vertices().find(|&v| mesh.edges_around_vertex(v).count() != degree[v.0]) {
+            return Err(VoronoiError::InvalidMesh(format!(
+                "the faces around vertex {} don't form a single fan", vertex.0
+            )));
+        }
+
+        Ok(mesh)
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn vertices(&self) -> impl Iterator<Item = VertexId> {
+        (0..self.vertices.len()).map(VertexId)
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = EdgeId> {
+        (0..self.edges.len()).map(EdgeId)
+    }
+
+    pub fn faces(&self) -> impl Iterator<Item = FaceId> {
+        (0..self.faces.len()).map(FaceId)
+    }
+
+    pub fn point(&self, vertex: VertexId) -> Point {
+        self.vertices[vertex.0].point
+    }
+
+    /// An outgoing half-edge of the vertex, or None if the vertex isn't part of any face
+    pub fn vertex_edge(&self, vertex: VertexId) -> Option<EdgeId> {
+        self.vertices[vertex.0].edge
+    }
+
+    /// One of the half-edges around the face
+    pub fn face_edge(&self, face: FaceId) -> EdgeId {
+        self.faces[face.0].edge
+    }
+
+    pub fn origin(&self, edge: EdgeId) -> VertexId {
+        self.edges[edge.0].origin
+    }
+
+    pub fn destination(&self, edge: EdgeId) -> VertexId {
+        self.origin(self.twin(edge))
+    }
+
+    pub fn twin(&self, edge: EdgeId) -> EdgeId {
+        self.edges[edge.0].twin
+    }
+
+    pub fn next(&self, edge: EdgeId) -> EdgeId {
+        self.edges[edge.0].next
+    }
+
+    pub fn prev(&self, edge: EdgeId) -> EdgeId {
+        self.edges[edge.0].prev
+    }
+
+    /// The face on the left of the half-edge, or None for edges on the outside of the mesh
+    pub fn face(&self, edge: EdgeId) -> Option<FaceId> {
+        self.edges[edge.0].face
+    }
+
+    pub fn is_boundary_edge(&self, edge: EdgeId) -> bool {
+        self.face(edge).is_none() || self.face(self.twin(edge)).is_none()
+    }
+
+    pub fn is_boundary_vertex(&self, vertex: VertexId) -> bool {
+        matches!(self.vertex_edge(vertex), Some(edge) if self.face(edge).is_none())
+    }
+
+    /// The outgoing half-edges of a vertex, in counter-clockwise order. For boundary vertices, the
+    /// first edge is the one with no face.
+    pub fn edges_around_vertex(&self, vertex: VertexId) -> EdgesAroundVertex<'_> {
+        let start = self.vertex_edge(vertex);
+
+        EdgesAroundVertex {
+            mesh: self,
+            start,
+            current: start,
+        }
+    }
+
+    /// The vertices connected to a vertex by an edge (its one-ring), in counter-clockwise order
+    pub fn vertex_neighbors(&self, vertex: VertexId) -> impl Iterator<Item = VertexId> + '_ {
+        self.edges_around_vertex(vertex).map(move |edge| self.destination(edge))
+    }
+
+    /// The faces that touch a vertex, in counter-clockwise order
+    pub fn faces_around_vertex(&self, vertex: VertexId) -> impl Iterator<Item = FaceId> + '_ {
+        self.edges_around_vertex(vertex).filter_map(move |edge| self.face(edge))
+    }
+
+    /// The half-edges around a face, in counter-clockwise order
+    pub fn face_edges(&self, face: FaceId) -> FaceEdges<'_> {
+        let start = self.face_edge(face);
+
+        FaceEdges {
+            mesh: self,
+            start,
+            current: Some(start),
+        }
+    }
+
+    /// The vertices of a face, in counter-clockwise order
+    pub fn face_vertices(&self, face: FaceId) -> impl Iterator<Item = VertexId> + '_ {
+        self.face_edges(face).map(move |edge| self.origin(edge))
+    }
+
+    /// The faces that share an edge with a face, in counter-clockwise order. Edges on the boundary
+    /// have no neighbor, so they are skipped.
+    pub fn face_neighbors(&self, face: FaceId) -> impl Iterator<Item = FaceId> + '_ {
+        self.face_edges(face).filter_map(move |edge| self.face(self.twin(edge)))
+    }
+}
+
+/// Iterator over the outgoing half-edges of a vertex. See Mesh::edges_around_vertex.
+pub struct EdgesAroundVertex<'a> {
+    mesh: &'a Mesh,
+    start: Option<EdgeId>,
+    current: Option<EdgeId>,
+}
+
+impl Iterator for EdgesAroundVertex<'_> {
+    type Item = EdgeId;
+
+    fn next(&mut self) -> Option<EdgeId> {
+        let edge = self.current?;
+
+        // The previous edge around the face on the left ends at this vertex, so its twin is the
+        // next outgoing edge counter-clockwise
+        let next = self.mesh.twin(self.mesh.prev(edge));
+        self.current = if Some(next) == self.start { None } else { Some(next) };
+
+        Some(edge)
+    }
+}
+
+/// Iterator over the half-edges of a face. See Mesh::face_edges.
+pub struct FaceEdges<'a> {
+    mesh: &'a Mesh,
+    start: EdgeId,
+    current: Option<EdgeId>,
+}
+
+impl Iterator for FaceEdges<'_> {
+    type Item = EdgeId;
+
+    fn next(&mut self) -> Option<EdgeId> {
+        let edge = self.current?;
+
+        let next = self.mesh.next(edge);
+        self.current = if next == self.start { None } else { Some(next) };
+
+        Some(edge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A unit square cut into four triangles around its center, vertex 4
+    fn fan() -> Mesh {
+        let points = [
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+            Point::new(0.5, 0.5),
+        ];
+
+        Mesh::from_faces(&points, &[[0, 1, 4], [1, 2, 4], [2, 3, 4], [3, 0, 4]]).unwrap()
+    }
+
+    /// Two triangles sharing no vertices, so the mesh has two boundary loops
+    fn islands() -> Mesh {
+        let points = [
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(0.0, 1.0),
+            Point::new(5.0, 0.0),
+            Point::new(6.0, 0.0),
+            Point::new(5.0, 1.0),
+        ];
+
+        Mesh::from_faces(&points, &[[0, 1, 2], [3, 4, 5]]).unwrap()
+    }
+
+    fn assert_invariants(mesh: &Mesh) {
+        for edge in mesh.edges() {
+            assert_ne!(mesh.twin(edge), edge);
+            assert_eq!(mesh.twin(mesh.twin(edge)), edge);
+            assert_eq!(mesh.next(mesh.prev(edge)), edge);
+            assert_eq!(mesh.prev(mesh.next(edge)), edge);
+            assert_eq!(mesh.origin(mesh.next(edge)), mesh.destination(edge));
+            assert_eq!(mesh.face(mesh.next(edge)), mesh.face(edge));
+            assert!(mesh.face(edge).is_some() || mesh.face(mesh.twin(edge)).is_some());
+        }
+
+        for face in mesh.faces() {
+            for edge in mesh.face_edges(face) {
+                assert_eq!(mesh.face(edge), Some(face));
+            }
+        }
+
+        for vertex in mesh.vertices() {
+            for edge in mesh.edges_around_vertex(vertex) {
+                assert_eq!(mesh.origin(edge), vertex);
+            }
+        }
+    }
+
+    /// The loops formed by following next from the half-edges with no face
+    fn boundary_loops(mesh: &Mesh) -> Vec<Vec<VertexId>> {
+        let mut seen = vec![false; mesh.edge_count()];
+        let mut loops = Vec::new();
+
+        for start in mesh.edges().filter(|&edge| mesh.face(edge).is_none()) {
+            if seen[start.0] {
+                continue;
+            }
+
+            let mut vertices = Vec::new();
+            let mut edge = start;
+            loop {
+                assert!(mesh.face(edge).is_none());
+                seen[edge.0] = true;
+                vertices.push(mesh.origin(edge));
+
+                edge = mesh.next(edge);
+                if edge == start {
+                    break;
+                }
+            }
+            loops.push(vertices);
+        }
+
+        loops
+    }
+
+    #[test]
+    fn counts() {
+        let mesh = fan();
+
+        assert_eq!(mesh.vertex_count(), 5);
+        assert_eq!(mesh.edge_count(), 16);
+        assert_eq!(mesh.face_count(), 4);
+    }
+
+    #[test]
+    fn invariants() {
+        assert_invariants(&fan());
+        assert_invariants(&islands());
+    }
+
+    #[test]
+    fn interior_vertex() {
+        let mesh = fan();
+        let center = VertexId(4);
+
+        assert!(!mesh.is_boundary_vertex(center));
+
+        // Counter-clockwise from wherever the walk starts
+        let neighbors: Vec<_> = mesh.vertex_neighbors(center).map(|v| v.0).collect();
+        let first = neighbors[0];
+        let expected: Vec<_> = (0..4).map(|i| (first + i) % 4).collect();
+        assert_eq!(neighbors, expected);
+
+        let faces: Vec<_> = mesh.faces_around_vertex(center).map(|f| f.0).collect();
+        let first = faces[0];
+        let expected: Vec<_> = (0..4).map(|i| (first + i) % 4).collect();
+        assert_eq!(faces, expected);
+    }
+
+    #[test]
+    fn boundary_vertex() {
+        let mesh = fan();
+        let corner = VertexId(0);
+
+        assert!(mesh.is_boundary_vertex(corner));
+
+        let first = mesh.edges_around_vertex(corner).next().unwrap();
+        assert_eq!(mesh.face(first), None);
+        assert!(mesh.is_boundary_edge(first));
+
+        let neighbors: Vec<_> = mesh.vertex_neighbors(corner).map(|v| v.0).collect();
+        assert_eq!(neighbors, vec![3, 1, 4]);
+
+        let faces: Vec<_> = mesh.faces_around_vertex(corner).map(|f| f.0).collect();
+        assert_eq!(faces, vec![0, 3]);
+    }
+
+    #[test]
+    fn faces() {
+        let mesh = fan();
+
+        let vertices: Vec<_> = mesh.face_vertices(FaceId(1)).map(|v| v.0).collect();
+        assert_eq!(vertices, vec![1, 2, 4]);
+
+        let neighbors: Vec<_> = mesh.face_neighbors(FaceId(0)).map(|f| f.0).collect();
+        assert_eq!(neighbors, vec![1, 3]);
+
+        let neighbors: Vec<_> = islands().face_neighbors(FaceId(0)).collect();
+        assert!(neighbors.is_empty());
+    }
+
+    #[test]
+    fn boundary() {
+        let mesh = fan();
+
+        // The outside runs clockwise around the square
+        let loops = boundary_loops(&mesh);
+        assert_eq!(loops.len(), 1);
+        let outside: Vec<_> = loops[0].iter().map(|v| v.0).collect();
+        let first = outside[0];
+        let expected: Vec<_> = (0..4).map(|i| (first + 4 - i) % 4).collect();
+        assert_eq!(outside, expected);
+
+        let boundary_edges = mesh.edges().filter(|&edge| mesh.is_boundary_edge(edge)).count();
+        assert_eq!(boundary_edges, 8);
+
+        let loops = boundary_loops(&islands());
+        assert_eq!(loops.len(), 2);
+        assert!(loops.iter().all(|vertices| vertices.len() == 3));
+    }
+
+    #[test]
+    fn isolated_vertex() {
+        let points = [Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(0.0, 1.0), Point::new(3.0, 3.0)];
+        let mesh = Mesh::from_faces(&points, &[[0, 1, 2]]).unwrap();
+
+        assert_eq!(mesh.vertex_edge(VertexId(3)), None);
+        assert!(!mesh.is_boundary_vertex(VertexId(3)));
+        assert_eq!(mesh.edges_around_vertex(VertexId(3)).count(), 0);
+    }
+
+    #[test]
+    fn invalid_faces() {
+        let points = [Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(0.0, 1.0), Point::new(1.0, 1.0)];
+        let invalid = |faces: &[&[usize]]| matches!(Mesh::from_faces(&points, faces), Err(VoronoiError::InvalidMesh(_)));
+
+        assert!(invalid(&[&[0, 1]]));
+        assert!(invalid(&[&[0, 1, 7]]));
+        // Both faces use 0 -> 1
+        assert!(invalid(&[&[0, 1, 2], &[0, 1, 3]]));
+    }
+
+    #[test]
+    fn pinched_vertex() {
+        // Two triangles that only meet at vertex 0
+        let points = [
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(-1.0, 0.0),
+            Point::new(-1.0, -1.0),
+        ];
+
+        let result = Mesh::from_faces(&points, &[[0, 1, 2], [0, 3, 4]]);
+        assert!(matches!(result, Err(VoronoiError::InvalidMesh(_))));
+    }
+}
@@ -0,0 +1,216 @@
+//!
+//! Ways of measuring the distance between two points. Changing the metric changes the shape of the
+//! Voronoi regions: Euclidean distance gives straight bisectors and convex polygons, while the
+//! Manhattan and Chebyshev metrics give bisectors made of horizontal, vertical and diagonal pieces.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::point::Point;
+
+/// A distance function between points. Implementations should be non-negative and symmetric, and
+/// zero between a point and itself. Nearest-site queries only compare distances, so any function
+/// that orders points the same way as a metric (like squared Euclidean distance) works too.
+pub trait Metric {
+    fn distance(&self, a: Point, b: Point) -> f64;
+}
+
+/// The straight-line (L2) distance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Euclidean;
+
+/// The square of the Euclidean distance. It is not a metric itself, but it is cheaper to compute,
+/// and ranks points in the same order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EuclideanSquared;
+
+/// The taxicab (L1) distance: the sum of the horizontal and vertical distances
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Manhattan;
+
+/// The chessboard (L∞) distance: the larger of the horizontal and vertical distances
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Chebyshev;
+
+/// The Lp distance, `(|dx|^p + |dy|^p)^(1/p)`. p = 1 is Manhattan, p = 2 is Euclidean, and an
+/// infinite p is Chebyshev. Values of p below 1 break the triangle inequality, but still give
+/// (star-shaped) Voronoi regions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minkowski {
+    pub p: f64,
+}
+
+/// Euclidean distance after a linear transformation, `|M (a - b)|`. Stretching one axis makes
+/// regions shrink along it, which models a medium that is harder to cross in some directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anisotropic {
+    pub matrix: [[f64; 2]; 2],
+}
+
+impl Minkowski {
+    pub fn new(p: f64) -> Minkowski {
+        Minkowski { p }
+    }
+}
+
+impl Anisotropic {
+    pub fn new(matrix: [[f64; 2]; 2]) -> Anisotropic {
+        Anisotropic { matrix }
+    }
+
+    /// Scales distances by `major` along the direction at `angle` radians (counter-clockwise from
+    /// the x axis), and by `minor` perpendicular to it
+    pub fn scaled(major: f64, minor: f64, angle: f64) -> Anisotropic {
+        let (sin, cos) = angle.sin_cos();
+
+        // Rotate the direction onto the x axis, scale, and leave it there: lengths are all that
+        // matter, so there is no need to rotate back
+        Anisotropic::new([
+            [major * cos, major * sin],
+            [-minor * sin, minor * cos],
+        ])
+    }
+}
+
+impl Metric for Euclidean {
+    fn distance(&self, a: Point, b: Point) -> f64 {
+        (a.x - b.x).hypot(a.y - b.y)
+    }
+}
+
+impl Metric for EuclideanSquared {
+    fn distance(&self, a: Point, b: Point) -> f64 {
+        let (dx, dy) = (a.x - b.x, a.y - b.y);
+
+        dx * dx + dy * dy
+    }
+}
+
+impl Metric for Manhattan {
+    fn distance(&self, a: Point, b: Point) -> f64 {
+        (a.x - b.x).abs() + (a.y - b.y).abs()
+    }
+}
+
+impl Metric for Chebyshev {
+    fn distance(&self, a: Point, b: Point) -> f64 {
+        (a.x - b.x).abs().max((a.y - b.y).abs())
+    }
+}
+
+impl Metric for Minkowski {
+    fn distance(&self, a: Point, b: Point) -> f64 {
+        let (dx, dy) = ((a.x - b.x).abs(), (a.y - b.y).abs());
+
+        if self.p == f64::INFINITY {
+            return dx.max(dy);
+        }
+
+        // Dividing by the larger difference keeps the powers from overflowing
+        let largest = dx.max(dy);
+        if largest == 0.0 {
+            return 0.0;
+        }
+        let (dx, dy) = (dx / largest, dy / largest);
+
+        largest * (dx.powf(self.p) + dy.powf(self.p)).powf(1.0 / self.p)
+    }
+}
+
+impl Metric for Anisotropic {
+    fn distance(&self, a: Point, b: Point) -> f64 {
+        let (dx, dy) = (a.x - b.x, a.y - b.y);
+        let [[m00, m01], [m10, m11]] = self.matrix;
+
+        (m00 * dx + m01 * dy).hypot(m10 * dx + m11 * dy)
+    }
+}
+
+impl<M: Metric + ?Sized> Metric for &M {
+    fn distance(&self, a: Point, b: Point) -> f64 {
+        (**self).distance(a, b)
+    }
+}
+
+impl<M: Metric + ?Sized> Metric for Box<M> {
+    fn distance(&self, a: Point, b: Point) -> f64 {
+        (**self).distance(a, b)
+    }
+}
+
+/// Finds the index of the site closest to p, or None if there are no sites. Ties go to the site
+/// that comes first.
+pub fn nearest_site<M: Metric + ?Sized>(sites: &[Point], p: Point, metric: &M) -> Option<usize> {
+    let mut closest = None;
+    let mut closest_distance = f64::INFINITY;
+
+    for (i, site) in sites.iter().enumerate() {
+        let distance = metric.distance(*site, p);
+
+        if closest.is_none() || distance < closest_distance {
+            closest = Some(i);
+            closest_distance = distance;
+        }
+    }
+
+    closest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{seeded_rng, uniform};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-12 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn known_distances() {
+        // 3 across and 4 up
+        let (a, b) = (Point::new(1.0, 2.0), Point::new(4.0, 6.0));
+
+        assert!(close(Euclidean.distance(a, b), 5.0));
+        assert!(close(EuclideanSquared.distance(a, b), 25.0));
+        assert!(close(Manhattan.distance(a, b), 7.0));
+        assert!(close(Chebyshev.distance(a, b), 4.0));
+        assert!(close(Minkowski::new(3.0).distance(a, b), 91f64.cbrt()));
+        assert!(close(Minkowski::new(f64::INFINITY).distance(a, b), 4.0));
+        assert!(close(Anisotropic::scaled(2.0, 1.0, 0.0).distance(a, b), 52f64.sqrt()));
+
+        for metric in [&Euclidean as &dyn Metric, &Manhattan, &Chebyshev, &Minkowski::new(0.5)].iter() {
+            assert_eq!(metric.distance(a, a), 0.0);
+            assert!(close(metric.distance(a, b), metric.distance(b, a)));
+        }
+    }
+
+    #[test]
+    fn minkowski_matches_manhattan_and_euclidean() {
+        let points = uniform(&mut seeded_rng(3), 50, -100.0..100.0, -100.0..100.0);
+
+        for pair in points.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert!(close(Minkowski::new(1.0).distance(a, b), Manhattan.distance(a, b)));
+            assert!(close(Minkowski::new(2.0).distance(a, b), Euclidean.distance(a, b)));
+        }
+    }
+
+    #[test]
+    fn nearest_depends_on_the_metric() {
+        // From the origin, (3, 3) is 6 away in L1 but 3 in L∞, and (4, 0) is 4 away in both
+        let sites = [Point::new(3.0, 3.0), Point::new(4.0, 0.0)];
+        let origin = Point::new(0.0, 0.0);
+
+        assert_eq!(nearest_site(&sites, origin, &Manhattan), Some(1));
+        assert_eq!(nearest_site(&sites, origin, &Chebyshev), Some(0));
+        assert_eq!(nearest_site(&sites, origin, &Euclidean), Some(1));
+    }
+
+    #[test]
+    fn nearest_ties_and_empty() {
+        let sites = [Point::new(-1.0, 0.0), Point::new(1.0, 0.0)];
+
+        assert_eq!(nearest_site(&sites, Point::new(0.0, 5.0), &Euclidean), Some(0));
+        assert_eq!(nearest_site(&[], Point::new(0.0, 0.0), &Euclidean), None);
+    }
+}
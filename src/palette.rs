@@ -0,0 +1,59 @@
+//!
+//! The colors the demos draw with, shared with the renderers that don't need a browser.
+//!
+//! https://bren.app/voronoi/
+//!
+
+/// The color the demos draw sites and outlines in
+pub const ACCENT: &str = "#fab1a0";
+
+/// Colors for filling cells, cycled through when there are more cells than colors
+pub const COLORS: &[&str] = &[
+    "#55efc4",
+    "#81ecec",
+    "#74b9ff",
+    "#dfe6e9",
+    "#ffeaa7",
+    "#fab1a0",
+    "#ff7675",
+    "#fd79a8",
+    "#636e72"
+];
+
+/// Reads a CSS color in one of the forms the renderers understand: "#rgb", "#rrggbb", "#rrggbbaa",
+/// "rgb(r, g, b)", "rgba(r, g, b, a)" (with alpha from 0 to 1), "transparent" or "none". Returns
+/// None for anything else, including color names.
+pub fn parse_color(color: &str) -> Option<[u8; 4]> {
+    let color = color.trim();
+    if color == "transparent" || color == "none" {
+        return Some([0, 0, 0, 0]);
+    }
+
+    if let Some(hex) = color.strip_prefix('#') {
+        if !hex.is_ascii() {
+            return None;
+        }
+
+        let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok();
+        let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return match hex.len() {
+            3 => Some([digit(0)? * 17, digit(1)? * 17, digit(2)? * 17, 255]),
+            6 => Some([pair(0)?, pair(2)?, pair(4)?, 255]),
+            8 => Some([pair(0)?, pair(2)?, pair(4)?, pair(6)?]),
+            _ => None,
+        };
+    }
+
+    let arguments = color
+        .strip_prefix("rgba(")
+        .or_else(|| color.strip_prefix("rgb("))?
+        .strip_suffix(')')?;
+    let values: Vec<f64> = arguments.split(',').map(|value| value.trim().parse().ok()).collect::<Option<_>>()?;
+    let channel = |value: f64| value.clamp(0.0, 255.0).round() as u8;
+
+    match values[..] {
+        [r, g, b] => Some([channel(r), channel(g), channel(b), 255]),
+        [r, g, b, a] => Some([channel(r), channel(g), channel(b), channel(a * 255.0)]),
+        _ => None,
+    }
+}
@@ -1,67 +1,92 @@
-///
-/// Represents a point in 2D space. Coordinates are stored as integers, so some computations will
-/// need to round to the nearest integer.
-///
-/// Author: Brendan McGuire
-/// Date: 18 July 2021
-///
-/// https://bren.app/voronoi/
-///
-use std::{fmt::Display, ops::Range};
+//!
+//! Represents a point in 2D space. Coordinates are stored as floating point numbers, so that
+//! constructed points (circumcenters, bisector intersections) are exact enough to build on. Canvas
+//! pixels are integers, but they are only converted at the point of rendering.
+//!
+//! Author: Brendan McGuire
+//! Date: 18 July 2021
+//!
+//! https://bren.app/voronoi/
+//!
+use std::{fmt::Display, hash::{Hash, Hasher}, ops::Range};
 use rand::prelude::*;
+use crate::metric::Metric;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Points are compared and hashed by their exact coordinates. Coordinates should be finite; NaN
+/// never compares equal, even to itself.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct Point {
-    pub x: i32,
-    pub y: i32,
-}
-pub enum Metric {
-    Euclidean,
-    EuclideanSquared,
-    Manhattan,
+    pub x: f64,
+    pub y: f64,
 }
 
 impl Point {
-    pub fn new(x: i32, y: i32) -> Point {
+    pub fn new(x: f64, y: f64) -> Point {
         Point { x, y }
     }
 
-    pub fn random(rng: &mut ThreadRng, x: Range<i32>, y: Range<i32>) -> Point {
+    /// A point chosen uniformly at random from a rectangle, with any random number generator (see
+    /// `generators::seeded_rng` for one that can be repeated)
+    pub fn random<R: Rng + ?Sized>(rng: &mut R, x: Range<f64>, y: Range<f64>) -> Point {
         let x = rng.gen_range(x);
         let y = rng.gen_range(y);
 
         Self::new(x, y)
     }
 
-    fn dist_euclidean(&self, other: &Point) -> f64 {
-        let diff_sq: f64 = self.dist_euclidean_squared(other);
-        let sqrt_diff = diff_sq.sqrt();
+    /// The distance to another point, measured with any metric
+    pub fn dist<M: Metric + ?Sized>(&self, other: &Point, metric: &M) -> f64 {
+        metric.distance(*self, *other)
+    }
+}
+
+/// A point with a weight, for weighted Voronoi diagrams. What the weight means depends on the
+/// distance: for power diagrams and regular triangulations it is a squared radius, for Apollonius
+/// diagrams it is a radius, and for multiplicatively weighted diagrams it is a scale. Either way,
+/// heavier points claim more space.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct WeightedPoint {
+    pub point: Point,
+    pub weight: f64,
+}
 
-        sqrt_diff
+impl WeightedPoint {
+    pub fn new(x: f64, y: f64, weight: f64) -> WeightedPoint {
+        WeightedPoint {
+            point: Point::new(x, y),
+            weight,
+        }
     }
 
-    fn dist_euclidean_squared(&self, other: &Point) -> f64 {
-        let x_diff: f64 = (self.x - other.x).into();
-        let y_diff: f64 = (self.y - other.y).into();
+    /// The power distance to p: `|p - point|² - weight`. It is negative inside the circle of
+    /// radius √weight around the point.
+    pub fn power(&self, p: Point) -> f64 {
+        let (dx, dy) = (p.x - self.point.x, p.y - self.point.y);
 
-        let diff_sq: f64 = (x_diff * x_diff + y_diff * y_diff).into();
+        dx * dx + dy * dy - self.weight
+    }
 
-        diff_sq
+    /// The additively weighted distance to p, `|p - point| - weight`, used by Apollonius diagrams.
+    /// With a positive weight, this is the distance to the circle of radius `weight` around the
+    /// point, and it is negative inside the circle.
+    pub fn additive_distance(&self, p: Point) -> f64 {
+        (p.x - self.point.x).hypot(p.y - self.point.y) - self.weight
     }
 
-    fn dist_manhattan(&self, other: &Point) -> f64 {
-        let x_diff = self.x - other.x;
-        let y_diff = self.y - other.y;
-        let diff_abs = x_diff + y_diff;
-        diff_abs.into()
+    /// The multiplicatively weighted distance to p, `|p - point| / weight`. The weight should be
+    /// positive.
+    pub fn multiplicative_distance(&self, p: Point) -> f64 {
+        (p.x - self.point.x).hypot(p.y - self.point.y) / self.weight
     }
+}
 
-    pub fn dist(&self, other: &Point, metric: Metric) -> f64 {
-        match metric {
-            Metric::Euclidean => self.dist_euclidean(other),
-            Metric::EuclideanSquared => self.dist_euclidean_squared(other),
-            Metric::Manhattan => self.dist_manhattan(other),
-        }
+impl Eq for Point {}
+
+impl Hash for Point {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Adding zero turns -0.0 into 0.0, since the two compare equal
+        (self.x + 0.0).to_bits().hash(state);
+        (self.y + 0.0).to_bits().hash(state);
     }
 }
 
@@ -0,0 +1,216 @@
+//!
+//! Poisson-disk sampling, with Bridson's algorithm: random points that are never closer together
+//! than a minimum distance, but otherwise fill the space. Uniform random points clump together and
+//! leave gaps, which makes tiny sliver cells next to huge ones. Poisson-disk points (also called
+//! blue noise) give cells of much the same size and shape, while still looking natural.
+//!
+//! Every accepted point stays active until it fails to place any of a fixed number of random
+//! candidates in the ring between one and two radii around it. The points are kept in a grid with
+//! cells so small that each holds at most one point, so checking a candidate only looks at a few
+//! cells around it, and the whole run takes time linear in the number of points.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use std::f64::consts::PI;
+use std::ops::Range;
+use rand::prelude::*;
+use crate::point::Point;
+use crate::polygon::{area, contains};
+
+/// How many candidates are tried around an active point before it is retired
+const CANDIDATES: usize = 30;
+
+/// The smallest share of its bounding box a polygon is taken to cover, which limits the tries at
+/// finding a start for polygons with (next to) no area
+const MIN_COVERAGE: f64 = 1e-4;
+
+/// Points in a rectangle that are at least `radius` apart
+pub fn poisson_disk<R: Rng + ?Sized>(rng: &mut R, x: Range<f64>, y: Range<f64>, radius: f64) -> Vec<Point> {
+    let rectangle = [
+        Point::new(x.start, y.start),
+        Point::new(x.end, y.start),
+        Point::new(x.end, y.end),
+        Point::new(x.start, y.end),
+    ];
+
+    poisson_disk_in_polygon(rng, &rectangle, radius)
+}
+
+/// Points inside a polygon (which doesn't need to be convex) that are at least `radius` apart
+pub fn poisson_disk_in_polygon<R: Rng + ?Sized>(rng: &mut R, polygon: &[Point], radius: f64) -> Vec<Point> {
+    sample(rng, polygon, radius, radius, |_| radius)
+}
+
+/// Points inside a polygon, spaced according to a density function. Where the density is 1 or
+/// more, points are at least `min_radius` apart; where it is lower, the spacing grows so that the
+/// number of points per unit area follows the density, up to `max_radius` where it is 0 or less.
+///
+/// No point is closer to another than the spacing at either of them.
+pub fn poisson_disk_with_density<R: Rng + ?Sized, F: Fn(Point) -> f64>(
+    rng: &mut R,
+    polygon: &[Point],
+    min_radius: f64,
+    max_radius: f64,
+    density: F,
+) -> Vec<Point> {
+    let max_radius = max_radius.max(min_radius);
+    let radius = |p: Point| {
+        let density = density(p);
+        if density > 0.0 {
+            (min_radius / density.sqrt()).clamp(min_radius, max_radius)
+        } else {
+            max_radius
+        }
+    };
+
+    sample(rng, polygon, min_radius, max_radius, radius)
+}
+
+/// Bridson's algorithm, with a radius that can change from point to point, between `min_radius`
+/// and `max_radius`
+fn sample<R: Rng + ?Sized, F: Fn(Point) -> f64>(rng: &mut R, polygon: &[Point], min_radius: f64, max_radius: f64, radius: F) -> Vec<Point> {
+    let mut points: Vec<Point> = Vec::new();
+    if polygon.len() < 3 || !min_radius.is_finite() || min_radius <= 0.0 || !max_radius.is_finite() {
+        return points;
+    }
+
+    let (mut x_min, mut y_min) = (f64::INFINITY, f64::INFINITY);
+    let (mut x_max, mut y_max) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for vertex in polygon {
+        x_min = x_min.min(vertex.x);
+        y_min = y_min.min(vertex.y);
+        x_max = x_max.max(vertex.x);
+        y_max = y_max.max(vertex.y);
+    }
+    let (width, height) = (x_max - x_min, y_max - y_min);
+    if !width.is_finite() || !height.is_finite() || width <= 0.0 || height <= 0.0 {
+        return points;
+    }
+
+    // Cells have a diagonal of min_radius, so no two points can share one
+    let size = min_radius / 2f64.sqrt();
+    let columns = (width / size).ceil() as usize + 1;
+    let rows = (height / size).ceil() as usize + 1;
+    let reach = (max_radius / size).ceil() as i64;
+    let mut grid: Vec<Option<usize>> = vec![None; columns * rows];
+    let cell = |p: Point| (((p.x - x_min) / size) as i64, ((p.y - y_min) / size) as i64);
+
+    // A candidate fits if it is in the polygon, and no point is closer to it than the radius at
+    // either of them
+    let fits = |p: Point, points: &[Point], grid: &[Option<usize>]| {
+        if !contains(polygon, p) {
+            return false;
+        }
+
+        let r = radius(p);
+        let (column, row) = cell(p);
+        for y in (row - reach).max(0)..=(row + reach).min(rows as i64 - 1) {
+            for x in (column - reach).max(0)..=(column + reach).min(columns as i64 - 1) {
+                if let Some(other) = grid[y as usize * columns + x as usize] {
+                    let q = points[other];
+                    let d = (p.x - q.x).hypot(p.y - q.y);
+                    if d < r || d < radius(q) {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        true
+    };
+
+    // The start is found by trying random points in the bounding box. A polygon that only covers
+    // a sliver of its box (like a thin diagonal strip) gets more tries, so that about CANDIDATES
+    // of them are still expected to land inside it.
+    let coverage = (area(polygon).abs() / (width * height)).max(MIN_COVERAGE);
+    let start_attempts = (CANDIDATES as f64 / coverage).ceil() as usize;
+
+    let mut active: Vec<usize> = Vec::new();
+    let accept = |p: Point, points: &mut Vec<Point>, grid: &mut [Option<usize>], active: &mut Vec<usize>| {
+        let (column, row) = cell(p);
+        grid[row as usize * columns + column as usize] = Some(points.len());
+        active.push(points.len());
+        points.push(p);
+    };
+
+    loop {
+        // Start from a random point in the polygon. This happens again whenever every point has
+        // been retired, to reach parts of the polygon the points so far couldn't spread into.
+        let start = (0..start_attempts)
+            .map(|_| Point::new(rng.gen_range(x_min..x_max), rng.gen_range(y_min..y_max)))
+            .find(|&p| fits(p, &points, &grid));
+        match start {
+            Some(p) => accept(p, &mut points, &mut grid, &mut active),
+            None => break,
+        }
+
+        while !active.is_empty() {
+            let i = rng.gen_range(0..active.len());
+            let p = points[active[i]];
+            let r = radius(p);
+
+            // Candidates are spread evenly by area over the ring from r to 2r
+            let candidate = (0..CANDIDATES)
+                .map(|_| {
+                    let distance = r * (1.0 + 3.0 * rng.gen::<f64>()).sqrt();
+                    let angle = 2.0 * PI * rng.gen::<f64>();
+                    Point::new(p.x + distance * angle.cos(), p.y + distance * angle.sin())
+                })
+                .find(|&q| fits(q, &points, &grid));
+
+            match candidate {
+                Some(q) => accept(q, &mut points, &mut grid, &mut active),
+                None => {
+                    active.swap_remove(i);
+                }
+            }
+        }
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::seeded_rng;
+
+    /// Checks that there are points, all inside the polygon and at least the radius apart
+    fn assert_spaced(polygon: &[Point], points: &[Point], radius: f64) {
+        assert!(!points.is_empty());
+        assert!(points.iter().all(|&p| contains(polygon, p)));
+
+        // Only points less than the radius apart in x need to be compared
+        let mut sorted = points.to_vec();
+        sorted.sort_by(|p, q| p.x.total_cmp(&q.x));
+        for (i, p) in sorted.iter().enumerate() {
+            for q in sorted[i + 1..].iter().take_while(|q| q.x - p.x < radius) {
+                assert!((p.x - q.x).hypot(p.y - q.y) >= radius, "{} and {} are too close", p, q);
+            }
+        }
+    }
+
+    #[test]
+    fn thin_diagonal_strip() {
+        // The strip covers less than 1% of its bounding box
+        let strip = [Point::new(0.0, 0.0), Point::new(6.0, 0.0), Point::new(1000.0, 994.0), Point::new(1000.0, 1000.0)];
+        for seed in 0..100 {
+            assert_spaced(&strip, &poisson_disk_in_polygon(&mut seeded_rng(seed), &strip, 5.0), 5.0);
+        }
+    }
+
+    #[test]
+    fn concave_polygon() {
+        // A comb with ten thin teeth, joined along the bottom
+        let mut comb = vec![Point::new(0.0, 0.0), Point::new(1000.0, 0.0)];
+        for tooth in (0..10).rev() {
+            let x = tooth as f64 * 100.0 + 95.0;
+            comb.extend([Point::new(x + 5.0, 1000.0), Point::new(x, 1000.0), Point::new(x, 5.0), Point::new(x - 95.0, 5.0)]);
+        }
+
+        for seed in 0..30 {
+            assert_spaced(&comb, &poisson_disk_in_polygon(&mut seeded_rng(seed), &comb, 10.0), 10.0);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//!
+//! Convex polygons, used as the region Voronoi cells are clipped to, and measurements of polygons
+//! given as a list of their vertices.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::error::VoronoiError;
+use crate::point::Point;
+use crate::predicates::orient2d;
+
+/// A convex polygon, with its vertices in counter-clockwise order (in a y-up coordinate system)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvexPolygon {
+    vertices: Vec<Point>,
+}
+
+impl ConvexPolygon {
+    /// Constructs a polygon from its vertices, in either clockwise or counter-clockwise order.
+    /// Fails if there are fewer than 3 vertices, a coordinate isn't finite, or the polygon is not
+    /// strictly convex (which includes repeated vertices, and three vertices in a row on a line).
+    pub fn new(vertices: Vec<Point>) -> Result<ConvexPolygon, VoronoiError> {
+        if vertices.len() < 3 {
+            return Err(VoronoiError::InvalidPolygon(format!("only {} vertices", vertices.len())));
+        }
+        if let Some(vertex) = vertices.iter().find(|v| !v.x.is_finite() || !v.y.is_finite()) {
+            return Err(VoronoiError::InvalidPolygon(format!("vertex {} is not finite", vertex)));
+        }
+
+        let mut vertices = vertices;
+        if orient2d(vertices[0], vertices[1], vertices[2]) < 0.0 {
+            vertices.reverse();
+        }
+
+        // Every corner has to turn left, and the vertices have to go around the first one exactly
+        // once (which rules out stars, whose corners all turn the same way too)
+        let n = vertices.len();
+        for i in 0..n {
+            let (a, b, c) = (vertices[i], vertices[(i + 1) % n], vertices[(i + 2) % n]);
+            if orient2d(a, b, c) <= 0.0 {
+                return Err(VoronoiError::InvalidPolygon(format!("not convex at vertex {}", b)));
+            }
+        }
+        for i in 1..n - 1 {
+            if orient2d(vertices[0], vertices[i], vertices[i + 1]) <= 0.0 {
+                return Err(VoronoiError::InvalidPolygon("the vertices wind around more than once".to_string()));
+            }
+        }
+
+        Ok(ConvexPolygon { vertices })
+    }
+
+    /// An axis-aligned rectangle. Fails if it has no area.
+    pub fn rectangle(x_min: f64, y_min: f64, x_max: f64, y_max: f64) -> Result<ConvexPolygon, VoronoiError> {
+        ConvexPolygon::new(vec![
+            Point::new(x_min, y_min),
+            Point::new(x_max, y_min),
+            Point::new(x_max, y_max),
+            Point::new(x_min, y_max),
+        ])
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    pub fn area(&self) -> f64 {
+        area(&self.vertices)
+    }
+
+    /// Returns true if the point is inside the polygon or on its boundary
+    pub fn contains(&self, p: Point) -> bool {
+        let n = self.vertices.len();
+        (0..n).all(|i| orient2d(self.vertices[i], self.vertices[(i + 1) % n], p) >= 0.0)
+    }
+
+    /// Clips another polygon to this one. The other polygon doesn't need to be convex, but if the
+    /// result has more than one piece, the pieces come out joined along this polygon's boundary.
+    pub fn clip(&self, polygon: &[Point], tolerance: f64) -> Vec<Point> {
+        let n = self.vertices.len();
+        let mut clipped = polygon.to_vec();
+
+        for i in 0..n {
+            let (a, b) = (self.vertices[i], self.vertices[(i + 1) % n]);
+
+            // Inside is to the left of each edge
+            clipped = clip_half_plane(&clipped, |p| (b.y - a.y) * (p.x - a.x) - (b.x - a.x) * (p.y - a.y), tolerance);
+            if clipped.is_empty() {
+                break;
+            }
+        }
+
+        clipped
+    }
+
+    /// Clips a polyline (an open chain of points) to this polygon. Returns the pieces of the
+    /// polyline inside the polygon, in order, each with at least two points.
+    pub fn clip_polyline(&self, polyline: &[Point]) -> Vec<Vec<Point>> {
+        let n = self.vertices.len();
+        let mut pieces = Vec::new();
+        let mut piece: Vec<Point> = Vec::new();
+
+        for segment in polyline.windows(2) {
+            let (p, q) = (segment[0], segment[1]);
+
+            // Cut the segment down to the part to the left of every edge, as the range t0..t1 of
+            // the way from p to q
+            let (mut t0, mut t1): (f64, f64) = (0.0, 1.0);
+            for i in 0..n {
+                let (a, b) = (self.vertices[i], self.vertices[(i + 1) % n]);
+                let side = |r: Point| (b.x - a.x) * (r.y - a.y) - (b.y - a.y) * (r.x - a.x);
+                let (dp, dq) = (side(p), side(q));
+
+                if dp < 0.0 && dq < 0.0 {
+                    t0 = 1.0;
+                    t1 = 0.0;
+                    break;
+                } else if dp < 0.0 {
+                    t0 = t0.max(dp / (dp - dq));
+                } else if dq < 0.0 {
+                    t1 = t1.min(dp / (dp - dq));
+                }
+            }
+
+            let at = |t: f64| Point::new(p.x + (q.x - p.x) * t, p.y + (q.y - p.y) * t);
+
+            // A segment that enters the polygon (or misses it) ends the piece before it
+            if t0 > 0.0 || t0 > t1 {
+                if piece.len() >= 2 {
+                    pieces.push(std::mem::take(&mut piece));
+                }
+                piece.clear();
+            }
+            if t0 > t1 {
+                continue;
+            }
+
+            if piece.is_empty() {
+                piece.push(at(t0));
+            }
+            piece.push(at(t1));
+
+            if t1 < 1.0 {
+                pieces.push(std::mem::take(&mut piece));
+            }
+        }
+
+        if piece.len() >= 2 {
+            pieces.push(piece);
+        }
+
+        pieces
+    }
+
+    /// The length of the longest side of the polygon's bounding box
+    pub fn extent(&self) -> f64 {
+        let (mut x_min, mut y_min) = (f64::INFINITY, f64::INFINITY);
+        let (mut x_max, mut y_max) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for vertex in self.vertices.iter() {
+            x_min = x_min.min(vertex.x);
+            y_min = y_min.min(vertex.y);
+            x_max = x_max.max(vertex.x);
+            y_max = y_max.max(vertex.y);
+        }
+
+        (x_max - x_min).max(y_max - y_min)
+    }
+}
+
+/// The signed area of a polygon: positive if its vertices are counter-clockwise, negative if they
+/// are clockwise. The polygon must not cross itself.
+pub fn area(polygon: &[Point]) -> f64 {
+    let n = polygon.len();
+    let twice: f64 = (0..n)
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+
+    twice / 2.0
+}
+
+/// Returns true if the point is inside a polygon, which doesn't need to be convex. Parts of a
+/// polygon that cross itself are inside if they are wound around an odd number of times. Points
+/// on the boundary may go either way.
+pub fn contains(polygon: &[Point], p: Point) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+
+        // Count the edges crossed by a ray going right from the point
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+/// The centroid (center of mass) of a polygon, or None if it has no area
+pub fn centroid(polygon: &[Point]) -> Option<Point> {
+    let n = polygon.len();
+    let (mut x, mut y, mut twice_area) = (0.0, 0.0, 0.0);
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        let cross = a.x * b.y - b.x * a.y;
+
+        x += (a.x + b.x) * cross;
+        y += (a.y + b.y) * cross;
+        twice_area += cross;
+    }
+
+    if twice_area == 0.0 {
+        return None;
+    }
+
+    Some(Point::new(x / (3.0 * twice_area), y / (3.0 * twice_area)))
+}
+
+/// Clips a polygon to the half-plane where `distance` is zero or less, with the Sutherland–Hodgman
+/// algorithm. `distance` has to be a linear function of the point (like the
+/// signed distance to a line), so that the crossing point can be interpolated.
+///
+/// Vertices closer together than the tolerance are merged, so that clipping through an existing
+/// vertex doesn't leave a sliver edge behind. Returns an empty polygon if nothing is left.
+pub(crate) fn clip_half_plane<F: Fn(Point) -> f64>(polygon: &[Point], distance: F, tolerance: f64) -> Vec<Point> {
+    let mut clipped: Vec<Point> = Vec::with_capacity(polygon.len() + 1);
+    let mut push = |p: Point| {
+        let repeated = matches!(clipped.last(), Some(last) if (last.x - p.x).hypot(last.y - p.y) <= tolerance);
+        if !repeated {
+            clipped.push(p);
+        }
+    };
+
+    for (i, &p) in polygon.iter().enumerate() {
+        let q = polygon[(i + 1) % polygon.len()];
+        let (dp, dq) = (distance(p), distance(q));
+
+        if dp <= 0.0 {
+            push(p);
+        }
+
+        // The edge crosses the line, so it is cut where the distance is zero
+        if (dp < 0.0 && dq > 0.0) || (dp > 0.0 && dq < 0.0) {
+            let t = dp / (dp - dq);
+            push(Point::new(p.x + (q.x - p.x) * t, p.y + (q.y - p.y) * t));
+        }
+    }
+
+    // The polygon is closed, so the last vertex can also repeat the first
+    if clipped.len() > 1 {
+        let (first, last) = (clipped[0], clipped[clipped.len() - 1]);
+        if (last.x - first.x).hypot(last.y - first.y) <= tolerance {
+            clipped.pop();
+        }
+    }
+
+    if clipped.len() < 3 {
+        clipped.clear();
+    }
+
+    clipped
+}
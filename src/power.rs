@@ -0,0 +1,181 @@
+//!
+//! The power diagram (or Laguerre diagram) of weighted sites, constructed as the dual of their
+//! regular triangulation. Each cell contains the points whose power distance to its site,
+//! `|p - site|² - weight`, is smallest. Bisectors are still straight lines (the radical axes of
+//! the circles around the sites) and cells are still convex, but a heavy site pushes the bisector
+//! towards its neighbors. A site can even lose its cell entirely, if it is hidden by heavier
+//! sites around it, and a cell doesn't always contain its own site.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::dulaney::{regular_triangulation, Triangulation};
+use crate::error::VoronoiError;
+use crate::mesh::VertexId;
+use crate::point::{Point, WeightedPoint};
+use crate::polygon::{clip_half_plane, ConvexPolygon};
+use crate::voronoi::{cell, Cell};
+
+/// The power diagram, along with the regular triangulation it is the dual of. Vertex i of the
+/// triangulation's mesh is sites[i], and the vertices of the diagram are the power centers of its
+/// faces. The cells of hidden sites are empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerDiagram {
+    pub sites: Vec<WeightedPoint>,
+    pub cells: Vec<Cell>,
+    pub triangulation: Triangulation,
+}
+
+impl PowerDiagram {
+    /// Builds the regular triangulation of the sites, and the power diagram from it. See
+    /// `regular_triangulation` for the ways this can fail.
+    pub fn new(sites: &[WeightedPoint]) -> Result<PowerDiagram, VoronoiError> {
+        let triangulation = regular_triangulation(sites)?;
+
+        let cells = triangulation
+            .mesh
+            .vertices()
+            .map(|vertex| {
+                cell(&triangulation, vertex, |face| {
+                    let corners: Vec<WeightedPoint> =
+                        triangulation.mesh.face_vertices(face).map(|vertex| sites[vertex.0]).collect();
+                    power_center(corners[0], corners[1], corners[2])
+                })
+            })
+            .collect();
+
+        Ok(PowerDiagram {
+            sites: sites.to_vec(),
+            cells,
+            triangulation,
+        })
+    }
+
+    /// Returns true if the site has no cell, because heavier sites around it cover its area
+    pub fn is_hidden(&self, site: usize) -> bool {
+        self.triangulation.mesh.vertex_edge(VertexId(site)).is_none()
+    }
+
+    /// The sites whose cells share an edge with the cell of a site
+    pub fn neighbors(&self, site: usize) -> impl Iterator<Item = usize> + '_ {
+        self.triangulation.mesh.vertex_neighbors(VertexId(site)).map(|vertex| vertex.0)
+    }
+
+    /// Clips the cell of every site to a convex region, giving one polygon per site (in the same
+    /// order as the sites). Each polygon is closed, with its vertices counter-clockwise and the
+    /// first vertex not repeated at the end. Cells of hidden sites, and cells entirely outside
+    /// the region, are empty.
+    pub fn clip(&self, region: &ConvexPolygon) -> Vec<Vec<Point>> {
+        (0..self.sites.len()).map(|site| self.clip_cell(site, region)).collect()
+    }
+
+    /// Clips the cell of a single site to a convex region. See `clip`.
+    pub fn clip_cell(&self, site: usize, region: &ConvexPolygon) -> Vec<Point> {
+        if self.is_hidden(site) {
+            return Vec::new();
+        }
+
+        let s = self.sites[site];
+        let tolerance = 1e-10 * region.extent();
+
+        // The cell is cut out of the region one radical axis at a time. Power distances differ by
+        // a linear function, so each side of an axis is a half-plane.
+        let mut polygon = region.vertices().to_vec();
+        for &neighbor in self.cells[site].neighbors.iter() {
+            let n = self.sites[neighbor];
+            let (mx, my) = ((s.point.x + n.point.x) / 2.0, (s.point.y + n.point.y) / 2.0);
+            let (dx, dy) = (n.point.x - s.point.x, n.point.y - s.point.y);
+            let shift = n.weight - s.weight;
+
+            // s.power(p) - n.power(p), without the squares that cancel out
+            polygon = clip_half_plane(&polygon, |p| 2.0 * ((p.x - mx) * dx + (p.y - my) * dy) + shift, tolerance);
+            if polygon.is_empty() {
+                break;
+            }
+        }
+
+        polygon
+    }
+}
+
+/// The power center of three weighted points, which has the same power distance to all three. It
+/// is the center of the circle orthogonal to the circles of radius √weight around each point. The
+/// points must not be collinear.
+pub fn power_center(a: WeightedPoint, b: WeightedPoint, c: WeightedPoint) -> Point {
+    let (bx, by) = (b.point.x - a.point.x, b.point.y - a.point.y);
+    let (cx, cy) = (c.point.x - a.point.x, c.point.y - a.point.y);
+
+    // Equal powers from a and b means 2 x·(b - a) = |b - a|² - b.weight + a.weight, relative to a
+    let b_lift = bx * bx + by * by - b.weight + a.weight;
+    let c_lift = cx * cx + cy * cy - c.weight + a.weight;
+    let d = 2.0 * (bx * cy - by * cx);
+
+    Point::new(
+        a.point.x + (cy * b_lift - by * c_lift) / d,
+        a.point.y + (bx * c_lift - cx * b_lift) / d,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use crate::generators::{seeded_rng, uniform};
+    use crate::polygon::contains;
+
+    #[test]
+    fn points_are_in_the_cell_of_their_nearest_site() {
+        let region = ConvexPolygon::rectangle(0.0, 0.0, 1000.0, 1000.0).unwrap();
+
+        for seed in 0..10 {
+            let mut rng = seeded_rng(seed);
+            let sites: Vec<WeightedPoint> = uniform(&mut rng, 100, 0.0..1000.0, 0.0..1000.0)
+                .into_iter()
+                .map(|p| WeightedPoint::new(p.x, p.y, rng.gen_range(0.0..5000.0)))
+                .collect();
+            let diagram = PowerDiagram::new(&sites).unwrap();
+            let cells = diagram.clip(&region);
+
+            for p in uniform(&mut rng, 2000, 0.0..1000.0, 0.0..1000.0) {
+                let mut powers: Vec<(f64, usize)> = sites.iter().enumerate().map(|(i, site)| (site.power(p), i)).collect();
+                powers.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                // Points right on an edge could go either way
+                if powers[1].0 - powers[0].0 < 1e-6 {
+                    continue;
+                }
+
+                let nearest = powers[0].1;
+                assert!(!diagram.is_hidden(nearest), "{} is nearest to hidden site {}", p, nearest);
+                assert!(contains(&cells[nearest], p), "{} is not in the cell of its nearest site {}", p, nearest);
+            }
+        }
+    }
+
+    #[test]
+    fn heavy_site_hides_a_light_one() {
+        let sites = [
+            WeightedPoint::new(0.0, 0.0, 0.0),
+            WeightedPoint::new(10.0, 0.0, 0.0),
+            WeightedPoint::new(0.0, 10.0, 0.0),
+            WeightedPoint::new(10.0, 10.0, 0.0),
+            WeightedPoint::new(5.0, 5.0, 100.0),
+            WeightedPoint::new(5.0, 6.0, 0.0),
+        ];
+        let diagram = PowerDiagram::new(&sites).unwrap();
+        let region = ConvexPolygon::rectangle(-10.0, -10.0, 20.0, 20.0).unwrap();
+
+        assert!(diagram.is_hidden(5));
+        assert!(diagram.clip_cell(5, &region).is_empty());
+        assert_eq!(diagram.neighbors(5).count(), 0);
+
+        for site in 0..5 {
+            assert!(!diagram.is_hidden(site));
+            assert!(!diagram.clip_cell(site, &region).is_empty());
+        }
+
+        // Even at the light site itself, the heavy one has the lower power
+        let light = Point::new(5.0, 6.0);
+        assert!(sites[4].power(light) < sites[5].power(light));
+    }
+}
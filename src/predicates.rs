@@ -0,0 +1,503 @@
+//!
+//! Robust geometric predicates, after Jonathan Shewchuk's "Adaptive Precision Floating-Point
+//! Arithmetic and Fast Robust Geometric Predicates" (https://www.cs.cmu.edu/~quake/robust.html).
+//!
+//! Both predicates are determinants whose sign answers a geometric question. They are first
+//! evaluated with plain floating point arithmetic, along with a bound on the rounding error. When
+//! the result is further from zero than the bound, its sign is certainly correct, which is almost
+//! always the case. Otherwise the determinant is evaluated again exactly, using expansions:
+//! sequences of floats whose (unrounded) sum is the exact value.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::point::{Point, WeightedPoint};
+
+/// Half of the machine epsilon, the largest relative error of a single rounded operation
+const EPSILON: f64 = f64::EPSILON / 2.0;
+
+/// 2^27 + 1, used to split a float into two halves that can be multiplied without error
+const SPLITTER: f64 = 134_217_729.0;
+
+const CCW_ERRBOUND: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+const ICC_ERRBOUND: f64 = (10.0 + 96.0 * EPSILON) * EPSILON;
+
+/// The in-circle bound, with room for the rounding in the weights
+const POWER_ERRBOUND: f64 = (12.0 + 128.0 * EPSILON) * EPSILON;
+
+/// Returns a positive value if a, b and c are in counter-clockwise order (in a y-up coordinate
+/// system), a negative value if they are clockwise, and zero if they are collinear. The magnitude
+/// is approximately twice the area of the triangle abc.
+pub fn orient2d(a: Point, b: Point, c: Point) -> f64 {
+    let detleft = (a.x - c.x) * (b.y - c.y);
+    let detright = (a.y - c.y) * (b.x - c.x);
+    let det = detleft - detright;
+
+    // If the two products have different signs, there is no cancellation, and the result is right
+    let detsum = if detleft > 0.0 {
+        if detright <= 0.0 {
+            return det;
+        }
+        detleft + detright
+    } else if detleft < 0.0 {
+        if detright >= 0.0 {
+            return det;
+        }
+        -detleft - detright
+    } else {
+        return det;
+    };
+
+    let errbound = CCW_ERRBOUND * detsum;
+    if det >= errbound || -det >= errbound {
+        return det;
+    }
+
+    orient2d_exact(a, b, c)
+}
+
+/// Returns a positive value if d lies inside the circle through a, b and c, a negative value if it
+/// lies outside, and zero if the four points are cocircular. The points a, b and c must be in
+/// counter-clockwise order, otherwise the sign is reversed.
+pub fn incircle(a: Point, b: Point, c: Point, d: Point) -> f64 {
+    let (adx, ady) = (a.x - d.x, a.y - d.y);
+    let (bdx, bdy) = (b.x - d.x, b.y - d.y);
+    let (cdx, cdy) = (c.x - d.x, c.y - d.y);
+
+    let (bdxcdy, cdxbdy) = (bdx * cdy, cdx * bdy);
+    let alift = adx * adx + ady * ady;
+
+    let (cdxady, adxcdy) = (cdx * ady, adx * cdy);
+    let blift = bdx * bdx + bdy * bdy;
+
+    let (adxbdy, bdxady) = (adx * bdy, bdx * ady);
+    let clift = cdx * cdx + cdy * cdy;
+
+    let det = alift * (bdxcdy - cdxbdy) + blift * (cdxady - adxcdy) + clift * (adxbdy - bdxady);
+
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * alift
+        + (cdxady.abs() + adxcdy.abs()) * blift
+        + (adxbdy.abs() + bdxady.abs()) * clift;
+
+    let errbound = ICC_ERRBOUND * permanent;
+    if det > errbound || -det > errbound {
+        return det;
+    }
+
+    incircle_exact(a, b, c, d)
+}
+
+/// The weighted version of incircle. Returns a positive value if d is closer to the orthogonal
+/// circle of a, b and c than its weight allows (so a, b and c can't be a triangle of the regular
+/// triangulation along with d), a negative value if it is further, and zero if d is exactly on
+/// the plane through the lifted points. The points a, b and c must be in counter-clockwise order,
+/// otherwise the sign is reversed. With all weights equal, this has the same sign as incircle.
+pub fn power_test(a: WeightedPoint, b: WeightedPoint, c: WeightedPoint, d: WeightedPoint) -> f64 {
+    let (adx, ady) = (a.point.x - d.point.x, a.point.y - d.point.y);
+    let (bdx, bdy) = (b.point.x - d.point.x, b.point.y - d.point.y);
+    let (cdx, cdy) = (c.point.x - d.point.x, c.point.y - d.point.y);
+
+    // Lifting each point to x² + y² - weight turns the question into an orientation in 3D
+    let (bdxcdy, cdxbdy) = (bdx * cdy, cdx * bdy);
+    let asquare = adx * adx + ady * ady;
+    let alift = asquare - (a.weight - d.weight);
+
+    let (cdxady, adxcdy) = (cdx * ady, adx * cdy);
+    let bsquare = bdx * bdx + bdy * bdy;
+    let blift = bsquare - (b.weight - d.weight);
+
+    let (adxbdy, bdxady) = (adx * bdy, bdx * ady);
+    let csquare = cdx * cdx + cdy * cdy;
+    let clift = csquare - (c.weight - d.weight);
+
+    let det = alift * (bdxcdy - cdxbdy) + blift * (cdxady - adxcdy) + clift * (adxbdy - bdxady);
+
+    let magnitude = |square: f64, w: f64| square + w.abs() + d.weight.abs();
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * magnitude(asquare, a.weight)
+        + (cdxady.abs() + adxcdy.abs()) * magnitude(bsquare, b.weight)
+        + (adxbdy.abs() + bdxady.abs()) * magnitude(csquare, c.weight);
+
+    let errbound = POWER_ERRBOUND * permanent;
+    if det > errbound || -det > errbound {
+        return det;
+    }
+
+    power_test_exact(a, b, c, d)
+}
+
+/// Exact orientation: ax·by - ax·cy - bx·ay + bx·cy + cx·ay - cx·by, with every product and sum
+/// kept without rounding.
+fn orient2d_exact(a: Point, b: Point, c: Point) -> f64 {
+    let terms = [
+        two_product(a.x, b.y),
+        two_product(-a.x, c.y),
+        two_product(-b.x, a.y),
+        two_product(b.x, c.y),
+        two_product(c.x, a.y),
+        two_product(-c.x, b.y),
+    ];
+
+    let det = terms
+        .iter()
+        .fold(vec![0.0], |sum, &(hi, lo)| expansion_sum(&sum, &[lo, hi]));
+
+    estimate(&det)
+}
+
+/// Exact in-circle test, expanding the 3x3 determinant of the points relative to d.
+fn incircle_exact(a: Point, b: Point, c: Point, d: Point) -> f64 {
+    let diff = |p: f64, q: f64| {
+        let (hi, lo) = two_sum(p, -q);
+        vec![lo, hi]
+    };
+
+    let (adx, ady) = (diff(a.x, d.x), diff(a.y, d.y));
+    let (bdx, bdy) = (diff(b.x, d.x), diff(b.y, d.y));
+    let (cdx, cdy) = (diff(c.x, d.x), diff(c.y, d.y));
+
+    let cross = |px: &[f64], py: &[f64], qx: &[f64], qy: &[f64]| {
+        expansion_sum(&multiply(px, qy), &negate(&multiply(qx, py)))
+    };
+    let lift = |px: &[f64], py: &[f64]| expansion_sum(&multiply(px, px), &multiply(py, py));
+
+    let bc = cross(&bdx, &bdy, &cdx, &cdy);
+    let ca = cross(&cdx, &cdy, &adx, &ady);
+    let ab = cross(&adx, &ady, &bdx, &bdy);
+
+    let det = expansion_sum(
+        &expansion_sum(&multiply(&lift(&adx, &ady), &bc), &multiply(&lift(&bdx, &bdy), &ca)),
+        &multiply(&lift(&cdx, &cdy), &ab),
+    );
+
+    estimate(&det)
+}
+
+/// Exact power test, the same as incircle_exact with the weights subtracted from the lifts
+fn power_test_exact(a: WeightedPoint, b: WeightedPoint, c: WeightedPoint, d: WeightedPoint) -> f64 {
+    let diff = |p: f64, q: f64| {
+        let (hi, lo) = two_sum(p, -q);
+        vec![lo, hi]
+    };
+
+    let (adx, ady) = (diff(a.point.x, d.point.x), diff(a.point.y, d.point.y));
+    let (bdx, bdy) = (diff(b.point.x, d.point.x), diff(b.point.y, d.point.y));
+    let (cdx, cdy) = (diff(c.point.x, d.point.x), diff(c.point.y, d.point.y));
+
+    let cross = |px: &[f64], py: &[f64], qx: &[f64], qy: &[f64]| {
+        expansion_sum(&multiply(px, qy), &negate(&multiply(qx, py)))
+    };
+    let lift = |px: &[f64], py: &[f64], weight: f64| {
+        let square = expansion_sum(&multiply(px, px), &multiply(py, py));
+        expansion_sum(&square, &diff(d.weight, weight))
+    };
+
+    let bc = cross(&bdx, &bdy, &cdx, &cdy);
+    let ca = cross(&cdx, &cdy, &adx, &ady);
+    let ab = cross(&adx, &ady, &bdx, &bdy);
+
+    let det = expansion_sum(
+        &expansion_sum(
+            &multiply(&lift(&adx, &ady, a.weight), &bc),
+            &multiply(&lift(&bdx, &bdy, b.weight), &ca),
+        ),
+        &multiply(&lift(&cdx, &cdy, c.weight), &ab),
+    );
+
+    estimate(&det)
+}
+
+/// Computes a + b, returning the rounded sum and the rounding error
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+
+    (x, (a - a_virtual) + (b - b_virtual))
+}
+
+/// Like two_sum, but only valid when |a| >= |b|
+fn fast_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+
+    (x, b - (x - a))
+}
+
+/// Splits a float into a high and low half, each with at most 26 significant bits
+fn split(a: f64) -> (f64, f64) {
+    let c = SPLITTER * a;
+    let hi = c - (c - a);
+
+    (hi, a - hi)
+}
+
+/// Computes a * b, returning the rounded product and the rounding error
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    let (a_hi, a_lo) = split(a);
+    let (b_hi, b_lo) = split(b);
+
+    let err1 = x - a_hi * b_hi;
+    let err2 = err1 - a_lo * b_hi;
+    let err3 = err2 - a_hi * b_lo;
+
+    (x, a_lo * b_lo - err3)
+}
+
+// Expansions are stored from the smallest magnitude component to the largest, with zeroes removed.
+// Zero itself is stored as [0.0].
+
+/// Adds a single float to an expansion
+fn grow_expansion(e: &[f64], b: f64) -> Vec<f64> {
+    let mut h = Vec::with_capacity(e.len() + 1);
+    let mut q = b;
+
+    for &component in e {
+        let (sum, err) = two_sum(q, component);
+        if err != 0.0 {
+            h.push(err);
+        }
+        q = sum;
+    }
+
+    if q != 0.0 || h.is_empty() {
+        h.push(q);
+    }
+
+    h
+}
+
+fn expansion_sum(e: &[f64], f: &[f64]) -> Vec<f64> {
+    f.iter().fold(e.to_vec(), |sum, &component| grow_expansion(&sum, component))
+}
+
+/// Multiplies an expansion by a single float
+fn scale_expansion(e: &[f64], b: f64) -> Vec<f64> {
+    let mut h = Vec::with_capacity(e.len() * 2);
+
+    let (mut q, err) = two_product(e[0], b);
+    if err != 0.0 {
+        h.push(err);
+    }
+
+    for &component in &e[1..] {
+        let (product_hi, product_lo) = two_product(component, b);
+
+        let (sum, err) = two_sum(q, product_lo);
+        if err != 0.0 {
+            h.push(err);
+        }
+
+        let (sum, err) = fast_two_sum(product_hi, sum);
+        if err != 0.0 {
+            h.push(err);
+        }
+        q = sum;
+    }
+
+    if q != 0.0 || h.is_empty() {
+        h.push(q);
+    }
+
+    h
+}
+
+fn multiply(e: &[f64], f: &[f64]) -> Vec<f64> {
+    f.iter().fold(vec![0.0], |sum, &component| expansion_sum(&sum, &scale_expansion(e, component)))
+}
+
+fn negate(e: &[f64]) -> Vec<f64> {
+    e.iter().map(|component| -component).collect()
+}
+
+/// Approximates the value of an expansion. The largest component dominates the sum of the others,
+/// so the sign is always exact.
+fn estimate(e: &[f64]) -> f64 {
+    e.iter().sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use crate::generators::seeded_rng;
+
+    /// Every coordinate in these tests is an integer (after scaling by a power of two) small
+    /// enough that the determinants can be worked out exactly in i128
+    fn exact(x: f64, scale: f64) -> i128 {
+        let scaled = x * scale;
+        assert_eq!(scaled.fract(), 0.0);
+        scaled as i128
+    }
+
+    fn orient2d_i128(a: Point, b: Point, c: Point, scale: f64) -> i128 {
+        let (ax, ay) = (exact(a.x, scale) - exact(c.x, scale), exact(a.y, scale) - exact(c.y, scale));
+        let (bx, by) = (exact(b.x, scale) - exact(c.x, scale), exact(b.y, scale) - exact(c.y, scale));
+        ax * by - ay * bx
+    }
+
+    /// Integer points exactly on the circle x² + y² = 5^38, which is about 2^88, in order around it.
+    /// They are the Gaussian integers (2 + i)^k (2 - i)^(38 - k), turned through each right angle.
+    fn on_circle() -> Vec<(i128, i128)> {
+        let mut points = Vec::new();
+        for k in 0..=38 {
+            let mut z = (1i128, 0i128);
+            for i in 0..38 {
+                let y = if i < k { 1 } else { -1 };
+                z = (2 * z.0 - y * z.1, 2 * z.1 + y * z.0);
+            }
+            for _ in 0..4 {
+                points.push(z);
+                z = (-z.1, z.0);
+            }
+        }
+        points.sort_by(|a, b| (a.1 as f64).atan2(a.0 as f64).total_cmp(&(b.1 as f64).atan2(b.0 as f64)));
+        points.dedup();
+
+        points
+    }
+
+    /// 5^38, the square of the radius of on_circle
+    const RADIUS_SQUARED: i128 = 363_797_880_709_171_295_166_015_625;
+
+    /// Integer points inside, outside and on the circle of on_circle, along with x² + y² - 5^38
+    fn near_circle(rng: &mut impl Rng, n: usize) -> Vec<(Point, i128)> {
+        let radius = 5i128.pow(19);
+        (0..n)
+            .map(|i| {
+                let x = rng.gen_range(-radius..=radius);
+                let mut y = ((RADIUS_SQUARED - x * x) as f64).sqrt() as i128;
+                while y * y > RADIUS_SQUARED - x * x {
+                    y -= 1;
+                }
+                while (y + 1) * (y + 1) <= RADIUS_SQUARED - x * x {
+                    y += 1;
+                }
+                let y = y + (i % 2) as i128;
+                (Point::new(x as f64, y as f64), x * x + y * y - RADIUS_SQUARED)
+            })
+            .collect()
+    }
+
+    fn sign(x: f64) -> i128 {
+        if x > 0.0 {
+            1
+        } else if x < 0.0 {
+            -1
+        } else {
+            0
+        }
+    }
+
+    /// The determinant of incircle, evaluated in plain floating point
+    fn incircle_naive(a: Point, b: Point, c: Point, d: Point) -> f64 {
+        let (adx, ady, bdx, bdy, cdx, cdy) = (a.x - d.x, a.y - d.y, b.x - d.x, b.y - d.y, c.x - d.x, c.y - d.y);
+        (adx * adx + ady * ady) * (bdx * cdy - cdx * bdy)
+            + (bdx * bdx + bdy * bdy) * (cdx * ady - adx * cdy)
+            + (cdx * cdx + cdy * cdy) * (adx * bdy - bdx * ady)
+    }
+
+    #[test]
+    fn orient2d_nearly_collinear() {
+        // Shewchuk's example: points a few ulps from the line through b and c, where plain floating
+        // point gets the sign wrong for a large share of them
+        let ulp = f64::EPSILON / 2.0;
+        let scale = 1.0 / ulp;
+        let (b, c) = (Point::new(12.0, 12.0), Point::new(24.0, 24.0));
+
+        let mut naive_wrong = 0;
+        for i in 0..64 {
+            for j in 0..64 {
+                let a = Point::new(0.5 + i as f64 * ulp, 0.5 + j as f64 * ulp);
+                let expected = orient2d_i128(a, b, c, scale).signum();
+                assert_eq!(sign(orient2d(a, b, c)), expected, "a = ({:e}, {:e})", a.x, a.y);
+
+                let naive = (a.x - c.x) * (b.y - c.y) - (a.y - c.y) * (b.x - c.x);
+                if sign(naive) != expected {
+                    naive_wrong += 1;
+                }
+            }
+        }
+        assert!(naive_wrong > 0);
+    }
+
+    #[test]
+    fn orient2d_exactly_collinear() {
+        // Large integer points on one line, where the products round but their difference is 0
+        let mut rng = seeded_rng(7);
+        for _ in 0..1000 {
+            let a = Point::new(rng.gen_range(-1e15..1e15f64).round(), rng.gen_range(-1e15..1e15f64).round());
+            let (dx, dy) = (rng.gen_range(-1000..1000) as f64, rng.gen_range(-1000..1000) as f64);
+            let (s, t) = (rng.gen_range(-1000..1000) as f64, rng.gen_range(-1000..1000) as f64);
+            let b = Point::new(a.x + s * dx, a.y + s * dy);
+            let c = Point::new(a.x + t * dx, a.y + t * dy);
+
+            assert_eq!(orient2d(a, b, c), 0.0);
+            assert_eq!(orient2d(c, b, a), 0.0);
+        }
+    }
+
+    #[test]
+    fn incircle_nearly_cocircular() {
+        // The corners are exactly on a circle of radius about 2^44, and d is the integer point just
+        // inside or just outside of it, which is closer than plain floating point can tell apart
+        let mut rng = seeded_rng(11);
+        let circle: Vec<Point> = on_circle().iter().map(|&(x, y)| Point::new(x as f64, y as f64)).collect();
+
+        let mut naive_wrong = 0;
+        for (d, power) in near_circle(&mut rng, 2000) {
+            let mut corners: Vec<usize> = rand::seq::index::sample(&mut rng, circle.len(), 3).into_vec();
+            corners.sort_unstable();
+            let [a, b, c] = [circle[corners[0]], circle[corners[1]], circle[corners[2]]];
+
+            let expected = -power.signum();
+            assert_eq!(sign(incircle(a, b, c, d)), expected, "d = {}", d);
+            assert_eq!(sign(incircle(b, a, c, d)), -expected);
+            if sign(incircle_naive(a, b, c, d)) != expected {
+                naive_wrong += 1;
+            }
+        }
+        assert!(naive_wrong > 0);
+    }
+
+    #[test]
+    fn incircle_exactly_cocircular() {
+        let mut rng = seeded_rng(3);
+        let circle: Vec<Point> = on_circle().iter().map(|&(x, y)| Point::new(x as f64, y as f64)).collect();
+
+        for _ in 0..1000 {
+            let mut corners: Vec<usize> = rand::seq::index::sample(&mut rng, circle.len(), 4).into_vec();
+            corners[..3].sort_unstable();
+            let [a, b, c, d] = [circle[corners[0]], circle[corners[1]], circle[corners[2]], circle[corners[3]]];
+            assert_eq!(incircle(a, b, c, d), 0.0);
+
+            // The same weight everywhere cancels out
+            let weighted = |p: Point| WeightedPoint::new(p.x, p.y, 1e6);
+            assert_eq!(power_test(weighted(a), weighted(b), weighted(c), weighted(d)), 0.0);
+        }
+    }
+
+    #[test]
+    fn power_test_nearly_degenerate() {
+        // With the corners weighted w, their orthogonal circle has a radius of R² - w, and d (with
+        // weight v) conflicts with them if x² + y² - v is less than that
+        let mut rng = seeded_rng(5);
+        let circle = on_circle();
+
+        let mut zeros = 0;
+        for (d, power) in near_circle(&mut rng, 2000) {
+            let mut corners: Vec<usize> = rand::seq::index::sample(&mut rng, circle.len(), 3).into_vec();
+            corners.sort_unstable();
+            let (w, v) = (rng.gen_range(-1i128 << 40..1i128 << 40), rng.gen_range(-1i128 << 40..1i128 << 40));
+            let [a, b, c] = [corners[0], corners[1], corners[2]].map(|i| WeightedPoint::new(circle[i].0 as f64, circle[i].1 as f64, w as f64));
+
+            // Sometimes move d onto the orthogonal circle exactly
+            let v = if rng.gen_bool(0.2) { power + w } else { v };
+            let expected = (v - w - power).signum();
+            if expected == 0 {
+                zeros += 1;
+            }
+            assert_eq!(sign(power_test(a, b, c, WeightedPoint::new(d.x, d.y, v as f64))), expected, "d = {}", d);
+        }
+        assert!(zeros > 0);
+    }
+}
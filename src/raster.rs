@@ -0,0 +1,238 @@
+//!
+//! Labels every pixel of a raster with its nearest site, by measuring the distance from each pixel
+//! to every site. This is the slowest way to draw a Voronoi diagram, but it works for any
+//! distance at all, including weighted distances whose cells have curved boundaries:
+//!
+//! - Apollonius (additively weighted) diagrams, with `WeightedPoint::additive_distance`
+//! - Multiplicatively weighted diagrams, with `WeightedPoint::multiplicative_distance`
+//! - Power diagrams, with `WeightedPoint::power`
+//! - Any metric, with `Metric::distance`
+//!
+//! The labels are kept in a plain buffer, so they can be drawn in one go (as an image), or used
+//! for anything else that needs to know which cell a pixel is in.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::point::Point;
+
+/// The label of a pixel with no nearest site
+pub const NO_SITE: u32 = u32::MAX;
+
+/// The nearest site to every pixel of a raster. Pixel (x, y) is measured at the point (x, y), and
+/// buffers are row by row, so pixel (x, y) is at index `y * width + x`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RasterVoronoi {
+    pub width: u32,
+    pub height: u32,
+
+    /// The index of the nearest site to each pixel, or NO_SITE if there are no sites, or every
+    /// distance is NaN. Ties go to the site that comes first.
+    pub labels: Vec<u32>,
+
+    /// The distance from each pixel to its nearest site, if it was asked for
+    pub distances: Option<Vec<f64>>,
+}
+
+impl RasterVoronoi {
+    /// Labels a `width` by `height` raster, where `distance(i, p)` is the distance from site i to
+    /// the point p, and there are `sites` sites
+    pub fn new<F: Fn(usize, Point) -> f64>(width: u32, height: u32, sites: usize, distance: F) -> RasterVoronoi {
+        Self::label(width, height, sites, distance, false)
+    }
+
+    /// Like new, but also keeps the distance from each pixel to its nearest site
+    pub fn with_distances<F: Fn(usize, Point) -> f64>(width: u32, height: u32, sites: usize, distance: F) -> RasterVoronoi {
+        Self::label(width, height, sites, distance, true)
+    }
+
+    fn label<F: Fn(usize, Point) -> f64>(width: u32, height: u32, sites: usize, distance: F, keep_distances: bool) -> RasterVoronoi {
+        let pixels = width as usize * height as usize;
+        let mut labels = Vec::with_capacity(pixels);
+        let mut distances = if keep_distances { Some(Vec::with_capacity(pixels)) } else { None };
+
+        for y in 0..height {
+            for x in 0..width {
+                let p = Point::new(x.into(), y.into());
+
+                let mut closest = NO_SITE;
+                let mut closest_distance = f64::INFINITY;
+                for i in 0..sites {
+                    let d = distance(i, p);
+
+                    if d < closest_distance || (closest == NO_SITE && d == closest_distance) {
+                        closest = i as u32;
+                        closest_distance = d;
+                    }
+                }
+
+                labels.push(closest);
+                if let Some(distances) = distances.as_mut() {
+                    distances.push(if closest == NO_SITE { f64::NAN } else { closest_distance });
+                }
+            }
+        }
+
+        RasterVoronoi {
+            width,
+            height,
+            labels,
+            distances,
+        }
+    }
+
+    /// The nearest site to pixel (x, y), or None if the pixel has no nearest site or is outside
+    /// the raster
+    pub fn label_at(&self, x: u32, y: u32) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        match self.labels[y as usize * self.width as usize + x as usize] {
+            NO_SITE => None,
+            label => Some(label as usize),
+        }
+    }
+
+    /// The distance from pixel (x, y) to its nearest site, if distances were kept and the pixel
+    /// has a nearest site
+    pub fn distance_at(&self, x: u32, y: u32) -> Option<f64> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let distance = self.distances.as_ref()?[y as usize * self.width as usize + x as usize];
+        if distance.is_nan() {
+            None
+        } else {
+            Some(distance)
+        }
+    }
+
+    /// The fraction of pixels (from 0 to 1) labelled differently from `exact`, for measuring an
+    /// approximation like `jfa::jump_flooding` against the labels from `RasterVoronoi::new`. If
+    /// both kept their distances, a different label at the same distance is a tie, not an error.
+    /// Rasters of different sizes don't match anywhere, so that is an error rate of 1.
+    pub fn error_rate(&self, exact: &RasterVoronoi) -> f64 {
+        if self.width != exact.width || self.height != exact.height {
+            return 1.0;
+        }
+        if self.labels.is_empty() {
+            return 0.0;
+        }
+
+        let errors = (0..self.labels.len())
+            .filter(|&i| {
+                self.labels[i] != exact.labels[i]
+                    && match (self.distances.as_ref(), exact.distances.as_ref()) {
+                        (Some(ours), Some(theirs)) => ours[i] != theirs[i],
+                        _ => true,
+                    }
+            })
+            .count();
+
+        errors as f64 / self.labels.len() as f64
+    }
+
+    /// Colors each pixel by its label, cycling through the colors, as RGBA bytes row by row (the
+    /// layout of a canvas's ImageData). Pixels with no nearest site are transparent.
+    pub fn to_rgba(&self, colors: &[[u8; 4]]) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.labels.len() * 4);
+
+        for &label in self.labels.iter() {
+            if label == NO_SITE || colors.is_empty() {
+                rgba.extend_from_slice(&[0, 0, 0, 0]);
+            } else {
+                rgba.extend_from_slice(&colors[label as usize % colors.len()]);
+            }
+        }
+
+        rgba
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metric::{Manhattan, Metric};
+
+    /// A 4 by 3 raster with sites in opposite corners. Under the Manhattan distance the odd
+    /// distance between them means no pixel is a tie.
+    fn corners(keep_distances: bool) -> RasterVoronoi {
+        let sites = [Point::new(0.0, 0.0), Point::new(3.0, 2.0)];
+        let distance = |i: usize, p: Point| Manhattan.distance(sites[i], p);
+
+        if keep_distances {
+            RasterVoronoi::with_distances(4, 3, sites.len(), distance)
+        } else {
+            RasterVoronoi::new(4, 3, sites.len(), distance)
+        }
+    }
+
+    #[test]
+    fn labels() {
+        let raster = corners(false);
+
+        let expected = [
+            0, 0, 0, 1,
+            0, 0, 1, 1,
+            0, 1, 1, 1,
+        ];
+        assert_eq!(raster.labels, expected);
+        assert_eq!(raster.label_at(2, 0), Some(0));
+        assert_eq!(raster.label_at(2, 1), Some(1));
+        assert_eq!(raster.label_at(4, 0), None);
+        assert_eq!(raster.label_at(0, 3), None);
+        assert_eq!(raster.distance_at(0, 0), None);
+    }
+
+    #[test]
+    fn distances() {
+        let raster = corners(true);
+
+        assert_eq!(raster.distance_at(0, 0), Some(0.0));
+        assert_eq!(raster.distance_at(2, 0), Some(2.0));
+        assert_eq!(raster.distance_at(1, 2), Some(2.0));
+        assert_eq!(raster.distance_at(3, 2), Some(0.0));
+        assert_eq!(raster.distance_at(3, 3), None);
+    }
+
+    #[test]
+    fn ties_and_missing_sites() {
+        // Pixel x = 1 is halfway between the sites
+        let sites = [Point::new(0.0, 0.0), Point::new(2.0, 0.0)];
+        let raster = RasterVoronoi::new(3, 2, 2, |i, p| Manhattan.distance(sites[i], p));
+        assert_eq!(raster.label_at(1, 0), Some(0));
+        assert_eq!(raster.label_at(1, 1), Some(0));
+
+        let empty = RasterVoronoi::with_distances(2, 2, 0, |_, _| 0.0);
+        assert!(empty.labels.iter().all(|&label| label == NO_SITE));
+        assert_eq!(empty.label_at(0, 0), None);
+        assert_eq!(empty.distance_at(0, 0), None);
+        assert_eq!(empty.to_rgba(&[[255, 0, 0, 255]]), vec![0; 16]);
+    }
+
+    #[test]
+    fn error_rate() {
+        let exact = corners(false);
+        assert_eq!(exact.error_rate(&exact), 0.0);
+
+        // 3 of 12 pixels wrong
+        let mut approximate = exact.clone();
+        for i in [0, 5, 11].iter() {
+            approximate.labels[*i] = 1 - approximate.labels[*i];
+        }
+        assert_eq!(approximate.error_rate(&exact), 0.25);
+
+        // With distances on both sides, a different label at the same distance is a tie
+        let exact = corners(true);
+        let mut approximate = exact.clone();
+        approximate.labels[0] = 1;
+        approximate.labels[1] = 1;
+        approximate.distances.as_mut().unwrap()[1] = 5.0;
+        assert_eq!(approximate.error_rate(&exact), 1.0 / 12.0);
+
+        let smaller = RasterVoronoi::new(3, 3, 0, |_, _| 0.0);
+        assert_eq!(smaller.error_rate(&exact), 1.0);
+    }
+}
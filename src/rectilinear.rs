@@ -0,0 +1,419 @@
+//!
+//! Voronoi diagrams under the Manhattan (L1) and Chebyshev (L∞) metrics. Bisectors under these
+//! metrics are polylines made of horizontal, vertical and 45° pieces, and cells are no longer
+//! convex, though each cell is still star-shaped around its site.
+//!
+//! The two metrics are the same up to a rotation: |dx| + |dy| = max(|dx + dy|, |dy - dx|), so L1
+//! distances are L∞ distances measured along the diagonals. Everything is built for L∞, and L1
+//! diagrams are built in a coordinate system rotated by 45°.
+//!
+//! Around a site s, the diagonals through it split the plane into four quarters (cones). Within a
+//! cone, the L∞ distance to s is just the distance r along the cone's axis, and another site t only
+//! claims the points with max(|r - a|, |h - b|) < r, where h is the offset across the axis and
+//! (a, b) is the offset of t in the same coordinates. Solving for r, the cell reaches out to
+//!
+//!   R(h) = min over t of max(a / 2, |h - b|)
+//!
+//! which is a lower envelope of V shapes with flat bottoms, so it only has slopes of 0 and ±1.
+//!
+//! # Ties
+//!
+//! Under L∞, two sites on the same horizontal or vertical line are the same distance from every
+//! point in two whole quarter-planes, rather than along a line. Under L1, the same happens for
+//! sites on the same diagonal. These regions are split by Euclidean distance: a point that is
+//! equally close to two sites goes to the one that is closer in a straight line. This cuts each
+//! tied region along the Euclidean bisector of the two sites, so every point belongs to exactly
+//! one cell (apart from the boundaries), and the cells stay star-shaped.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::error::{validate_sites, VoronoiError};
+use crate::grid::Grid;
+use crate::point::Point;
+use crate::polygon::ConvexPolygon;
+
+/// Computes the cells of the Chebyshev (L∞) Voronoi diagram, clipped to a region. Returns one
+/// polygon for each site, in the same order as the sites. Each polygon is closed, with its
+/// vertices counter-clockwise and the first vertex not repeated at the end. Cells entirely outside
+/// the region are empty.
+///
+/// Fails if there are no sites, a coordinate is out of range, or a site is repeated.
+pub fn chebyshev_diagram(sites: &[Point], region: &ConvexPolygon) -> Result<Vec<Vec<Point>>, VoronoiError> {
+    validate_sites(sites)?;
+
+    Ok(clipped_cells(sites, region, |p| p, |p| p))
+}
+
+/// Computes the cells of the Manhattan (L1) Voronoi diagram, clipped to a region. See
+/// `chebyshev_diagram` for the format of the result, and the ways this can fail.
+pub fn manhattan_diagram(sites: &[Point], region: &ConvexPolygon) -> Result<Vec<Vec<Point>>, VoronoiError> {
+    validate_sites(sites)?;
+
+    // Rotating by 45° (and scaling by √2) turns L1 distances into L∞ distances
+    let rotate = |p: Point| Point::new(p.x + p.y, p.y - p.x);
+    let unrotate = |p: Point| Point::new((p.x - p.y) / 2.0, (p.x + p.y) / 2.0);
+
+    Ok(clipped_cells(sites, region, rotate, unrotate))
+}
+
+/// Builds the L∞ cells of the sites after transforming them forward, then transforms the cells back
+/// and clips them to the region
+fn clipped_cells<F, B>(sites: &[Point], region: &ConvexPolygon, forward: F, back: B) -> Vec<Vec<Point>>
+where
+    F: Fn(Point) -> Point,
+    B: Fn(Point) -> Point,
+{
+    let transformed: Vec<Point> = sites.iter().map(|&site| forward(site)).collect();
+    let corners: Vec<Point> = region.vertices().iter().map(|&corner| forward(corner)).collect();
+    let grid = Grid::new(&transformed);
+    let tolerance = 1e-10 * region.extent();
+
+    (0..sites.len())
+        .map(|i| {
+            // Cells are cut off far enough out that the cut is entirely outside the region
+            let s = transformed[i];
+            let cap = 2.0 * corners
+                .iter()
+                .map(|c| (c.x - s.x).abs().max((c.y - s.y).abs()))
+                .fold(0.0, f64::max);
+
+            let cell: Vec<Point> = cell(i, &transformed, &grid, cap).into_iter().map(&back).collect();
+            region.clip(&cell, tolerance)
+        })
+        .collect()
+}
+
+/// The L∞ cell of a site, cut off at a distance of `cap` from it
+fn cell(site: usize, sites: &[Point], grid: &Grid, cap: f64) -> Vec<Point> {
+    let s = sites[site];
+    let mut envelopes = [Envelope::new(cap), Envelope::new(cap), Envelope::new(cap), Envelope::new(cap)];
+    let mut reach = [cap; 4];
+
+    // Visit the other sites from nearest to furthest, a ring of buckets at a time. A site at least
+    // twice as far as a cone reaches can't change that cone.
+    for ring in 0.. {
+        let nearest = (ring as f64 - 1.0) * grid.size;
+        let furthest_reach = reach.iter().copied().fold(0.0, f64::max);
+        if nearest >= 2.0 * furthest_reach || !grid.has_ring(s, ring) {
+            break;
+        }
+
+        for t in grid.ring(s, ring) {
+            if t == site {
+                continue;
+            }
+
+            for (cone, envelope) in envelopes.iter_mut().enumerate() {
+                let (a, b) = to_cone(cone, sites[t].x - s.x, sites[t].y - s.y);
+                envelope.claim(a, b, reach[cone]);
+            }
+        }
+
+        for (cone, envelope) in envelopes.iter().enumerate() {
+            reach[cone] = envelope.reach();
+        }
+    }
+
+    let mut polygon: Vec<Point> = Vec::new();
+    let tolerance = 1e-12 * cap;
+    for (cone, envelope) in envelopes.iter().enumerate() {
+        for (h, r) in envelope.chain() {
+            let (dx, dy) = from_cone(cone, r, h);
+            let p = Point::new(s.x + dx, s.y + dy);
+
+            let repeated = matches!(polygon.last(), Some(last) if (last.x - p.x).abs().max((last.y - p.y).abs()) <= tolerance);
+            if !repeated {
+                polygon.push(p);
+            }
+        }
+    }
+
+    if polygon.len() > 1 {
+        let (first, last) = (polygon[0], polygon[polygon.len() - 1]);
+        if (last.x - first.x).abs().max((last.y - first.y).abs()) <= tolerance {
+            polygon.pop();
+        }
+    }
+
+    polygon
+}
+
+/// Converts an offset from the site into the coordinates of a cone: the distance along its axis,
+/// and the offset across it. Cone 0 points right, and the others follow counter-clockwise.
+fn to_cone(cone: usize, dx: f64, dy: f64) -> (f64, f64) {
+    (0..cone).fold((dx, dy), |(x, y), _| (y, -x))
+}
+
+/// Converts the coordinates of a cone back into an offset from the site
+fn from_cone(cone: usize, r: f64, h: f64) -> (f64, f64) {
+    (0..cone).fold((r, h), |(x, y), _| (-y, x))
+}
+
+/// A linear piece of the envelope, from (h0, r0) to (h1, r1)
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    h0: f64,
+    h1: f64,
+    r0: f64,
+    r1: f64,
+}
+
+impl Piece {
+    fn at(&self, h: f64) -> f64 {
+        if self.h1 == self.h0 {
+            return self.r0;
+        }
+
+        self.r0 + (self.r1 - self.r0) * (h - self.h0) / (self.h1 - self.h0)
+    }
+}
+
+/// How far a cell reaches along the axis of one cone, R(h), as a function of the offset h across
+/// it. Pieces are in order and cover h from -cap to cap without gaps, but the envelope can jump
+/// where a tie is split.
+#[derive(Debug, Clone)]
+struct Envelope {
+    pieces: Vec<Piece>,
+}
+
+impl Envelope {
+    fn new(cap: f64) -> Envelope {
+        Envelope {
+            pieces: vec![Piece { h0: -cap, h1: cap, r0: cap, r1: cap }],
+        }
+    }
+
+    /// Lowers the envelope to account for a site at (a, b) in the cone's coordinates. Sites that
+    /// can't reach the part of the cell within `reach` are skipped.
+    fn claim(&mut self, a: f64, b: f64, reach: f64) {
+        let cap = self.pieces[self.pieces.len() - 1].h1;
+
+        // On the left, the distance falls towards b, then (for a > 0) levels off at a / 2, and
+        // rises again on the right
+        let v = |h0: f64, h1: f64| {
+            let r = |h: f64| (h - b).abs().max(a / 2.0);
+            Piece { h0, h1, r0: r(h0), r1: r(h1) }
+        };
+
+        let cup = if a > 0.0 {
+            if a / 2.0 >= reach || b.abs() - reach >= reach {
+                return;
+            }
+            let c = a / 2.0;
+            [v(-cap, b - c), v(b - c, b + c), v(b + c, cap)]
+        } else if a == 0.0 && b != 0.0 {
+
+            // The sites are level across this cone, so they tie wherever t is at least as close
+            // along the axis. The tie goes to t only on its side of their Euclidean bisector.
+            if b.abs() / 2.0 >= reach {
+                return;
+            }
+            if b > 0.0 {
+                [v(b / 2.0, b), v(b, cap), v(cap, cap)]
+            } else {
+                [v(-cap, -cap), v(-cap, b), v(b, b / 2.0)]
+            }
+        } else {
+            return;
+        };
+
+        let mut lowered = Vec::with_capacity(self.pieces.len() + 4);
+        for piece in self.pieces.iter() {
+            let mut h = piece.h0;
+
+            for other in cup.iter() {
+                let (start, end) = (other.h0.max(h), other.h1.min(piece.h1));
+                if start >= end {
+                    continue;
+                }
+
+                // Any gap before the cup starts is left alone
+                if start > h {
+                    lowered.push(Piece { h0: h, h1: start, r0: piece.at(h), r1: piece.at(start) });
+                }
+                Self::lower(&mut lowered, piece, other, start, end);
+                h = end;
+            }
+
+            if h < piece.h1 {
+                lowered.push(Piece { h0: h, h1: piece.h1, r0: piece.at(h), r1: piece.r1 });
+            }
+        }
+
+        self.pieces = lowered;
+        self.simplify();
+    }
+
+    /// Pushes the lower of two pieces between start and end, splitting where they cross
+    fn lower(pieces: &mut Vec<Piece>, a: &Piece, b: &Piece, start: f64, end: f64) {
+        let (a0, a1) = (a.at(start), a.at(end));
+        let (b0, b1) = (b.at(start), b.at(end));
+        let (d0, d1) = (a0 - b0, a1 - b1);
+
+        if d0 <= 0.0 && d1 <= 0.0 {
+            pieces.push(Piece { h0: start, h1: end, r0: a0, r1: a1 });
+        } else if d0 >= 0.0 && d1 >= 0.0 {
+            pieces.push(Piece { h0: start, h1: end, r0: b0, r1: b1 });
+        } else {
+            let cross = start + (end - start) * d0 / (d0 - d1);
+            let r = a.at(cross);
+
+            let (first, second) = if d0 < 0.0 { ((a0, r), (r, b1)) } else { ((b0, r), (r, a1)) };
+            pieces.push(Piece { h0: start, h1: cross, r0: first.0, r1: first.1 });
+            pieces.push(Piece { h0: cross, h1: end, r0: second.0, r1: second.1 });
+        }
+    }
+
+    /// Merges neighboring pieces that continue in a straight line, and drops empty ones
+    fn simplify(&mut self) {
+        let mut merged: Vec<Piece> = Vec::with_capacity(self.pieces.len());
+
+        for piece in self.pieces.iter() {
+            if piece.h1 <= piece.h0 {
+                continue;
+            }
+
+            if let Some(last) = merged.last_mut() {
+                let slope = |p: &Piece| (p.r1 - p.r0) / (p.h1 - p.h0);
+                let continuous = (last.r1 - piece.r0).abs() <= 1e-12 * last.h1.abs().max(piece.r0.abs());
+
+                if continuous && (slope(last) - slope(piece)).abs() <= 1e-9 {
+                    last.h1 = piece.h1;
+                    last.r1 = piece.r1;
+                    continue;
+                }
+            }
+
+            merged.push(*piece);
+        }
+
+        self.pieces = merged;
+    }
+
+    /// Where the envelope meets the edges of the cone (R(h) = |h|) on either side. Between these,
+    /// the cell reaches out to the envelope.
+    fn limits(&self) -> (f64, f64) {
+        // R(h) - h only falls as h grows past 0, and R(h) + h only falls as h shrinks below 0
+        let mut high = self.pieces[self.pieces.len() - 1].h1;
+        for piece in self.pieces.iter().filter(|piece| piece.h1 > 0.0) {
+            let start = piece.h0.max(0.0);
+            let (f0, f1) = (piece.at(start) - start, piece.r1 - piece.h1);
+
+            if f0 <= 0.0 {
+                high = start;
+                break;
+            }
+            if f1 <= 0.0 {
+                high = start + (piece.h1 - start) * f0 / (f0 - f1);
+                break;
+            }
+        }
+
+        let mut low = self.pieces[0].h0;
+        for piece in self.pieces.iter().rev().filter(|piece| piece.h0 < 0.0) {
+            let end = piece.h1.min(0.0);
+            let (f1, f0) = (piece.at(end) + end, piece.r0 + piece.h0);
+
+            if f1 <= 0.0 {
+                low = end;
+                break;
+            }
+            if f0 <= 0.0 {
+                low = end - (end - piece.h0) * f1 / (f1 - f0);
+                break;
+            }
+        }
+
+        (low, high)
+    }
+
+    /// The outline of the cell in this cone, as (h, r) points from one edge of the cone to the other
+    fn chain(&self) -> Vec<(f64, f64)> {
+        let (low, high) = self.limits();
+
+        let mut chain = vec![(low, -low)];
+        for piece in self.pieces.iter().filter(|piece| piece.h1 > low && piece.h0 < high) {
+            let (start, end) = (piece.h0.max(low), piece.h1.min(high));
+            chain.push((start, piece.at(start)));
+            chain.push((end, piece.at(end)));
+        }
+        chain.push((high, high));
+
+        chain
+    }
+
+    /// How far the cell reaches along the axis, anywhere in this cone
+    fn reach(&self) -> f64 {
+        self.chain().iter().map(|&(_, r)| r).fold(0.0, f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{jittered_grid, seeded_rng, uniform};
+    use crate::polygon::contains;
+
+    /// Checks that sampled points are in the cell of their nearest site, with ties between sites
+    /// going to the one that is closer in a straight line
+    fn assert_nearest(sites: &[Point], cells: &[Vec<Point>], distance: impl Fn(Point, Point) -> f64, samples: &[Point]) {
+        for &p in samples {
+            let distances: Vec<f64> = sites.iter().map(|&site| distance(p, site)).collect();
+            let nearest = distances.iter().copied().fold(f64::INFINITY, f64::min);
+
+            // Sites tie if their distances only differ by rounding
+            let mut tied: Vec<(f64, usize)> = (0..sites.len())
+                .filter(|&i| distances[i] - nearest < 1e-9)
+                .map(|i| ((p.x - sites[i].x).hypot(p.y - sites[i].y), i))
+                .collect();
+            tied.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            // Points right on an edge could go either way
+            let runner_up = distances.iter().copied().filter(|&d| d - nearest >= 1e-9).fold(f64::INFINITY, f64::min);
+            if runner_up - nearest < 1e-6 || (tied.len() > 1 && tied[1].0 - tied[0].0 < 1e-6) {
+                continue;
+            }
+
+            let owner = tied[0].1;
+            assert!(contains(&cells[owner], p), "{} is not in the cell of its nearest site {}", p, owner);
+        }
+    }
+
+    fn chebyshev(p: Point, q: Point) -> f64 {
+        (p.x - q.x).abs().max((p.y - q.y).abs())
+    }
+
+    fn manhattan(p: Point, q: Point) -> f64 {
+        (p.x - q.x).abs() + (p.y - q.y).abs()
+    }
+
+    #[test]
+    fn random_sites() {
+        let region = ConvexPolygon::rectangle(0.0, 0.0, 1000.0, 1000.0).unwrap();
+
+        for seed in 0..10 {
+            let mut rng = seeded_rng(seed);
+            let sites = uniform(&mut rng, 100, 0.0..1000.0, 0.0..1000.0);
+            let samples = uniform(&mut rng, 2000, 0.0..1000.0, 0.0..1000.0);
+
+            assert_nearest(&sites, &chebyshev_diagram(&sites, &region).unwrap(), chebyshev, &samples);
+            assert_nearest(&sites, &manhattan_diagram(&sites, &region).unwrap(), manhattan, &samples);
+        }
+    }
+
+    #[test]
+    fn tied_sites() {
+        // Sites in rows and columns tie under L∞, and sites on diagonals tie under L1
+        let region = ConvexPolygon::rectangle(0.0, 0.0, 1000.0, 1000.0).unwrap();
+        let mut rng = seeded_rng(1);
+        let grid = jittered_grid(&mut rng, 7, 5, 0.0..1000.0, 0.0..1000.0, 0.0);
+        let diagonal: Vec<Point> = (0..8).map(|i| Point::new(60.0 + 120.0 * i as f64, 60.0 + 120.0 * i as f64)).collect();
+        let samples = uniform(&mut rng, 4000, 0.0..1000.0, 0.0..1000.0);
+
+        for sites in [grid, diagonal] {
+            assert_nearest(&sites, &chebyshev_diagram(&sites, &region).unwrap(), chebyshev, &samples);
+            assert_nearest(&sites, &manhattan_diagram(&sites, &region).unwrap(), manhattan, &samples);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//!
+//! Draws on an HTML canvas, through its 2D context. Only available with the `wasm` feature.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+use crate::error::VoronoiError;
+use crate::image::Image;
+use crate::point::Point;
+use super::{Renderer, Style};
+
+/// A renderer for a canvas. Clones draw on the same canvas, so one can be moved into an event
+/// handler while another is kept.
+#[derive(Debug, Clone)]
+pub struct CanvasRenderer {
+    pub context: Rc<CanvasRenderingContext2d>,
+    width: f64,
+    height: f64,
+    style: Style,
+}
+
+impl CanvasRenderer {
+    /// A renderer for the whole canvas, starting out drawing solid lines in the accent color
+    pub fn new(canvas: &HtmlCanvasElement) -> Result<CanvasRenderer, VoronoiError> {
+        let context = canvas
+            .get_context("2d")?
+            .ok_or_else(|| VoronoiError::Render("Canvas has no 2d context".into()))?
+            .dyn_into::<CanvasRenderingContext2d>()
+            .map_err(|_| VoronoiError::Render("Could not get canvas context".into()))?;
+
+        let mut renderer = CanvasRenderer {
+            context: Rc::new(context),
+            width: canvas.width() as f64,
+            height: canvas.height() as f64,
+            style: Style::stroke(crate::palette::ACCENT, 4.0),
+        };
+        let style = renderer.style.clone();
+        renderer.style(&style)?;
+
+        Ok(renderer)
+    }
+
+    /// Fills and strokes the current path, as the style asks
+    fn paint(&self, fill: bool) {
+        if fill && self.style.fill.is_some() {
+            self.context.fill();
+        }
+        if self.style.stroke.is_some() {
+            self.context.stroke();
+        }
+    }
+}
+
+impl Renderer for CanvasRenderer {
+    fn clear(&mut self) -> Result<(), VoronoiError> {
+        self.context.clear_rect(0.0, 0.0, self.width, self.height);
+        Ok(())
+    }
+
+    fn style(&mut self, style: &Style) -> Result<(), VoronoiError> {
+        if let Some(stroke) = &style.stroke {
+            self.context.set_stroke_style_str(stroke);
+        }
+        if let Some(fill) = &style.fill {
+            self.context.set_fill_style_str(fill);
+        }
+        self.context.set_line_width(style.stroke_width);
+        self.context.set_global_alpha(style.opacity);
+
+        let dash: js_sys::Array = style.dash.iter().map(|&length| JsValue::from_f64(length)).collect();
+        self.context.set_line_dash(&dash)?;
+
+        self.style = style.clone();
+        Ok(())
+    }
+
+    fn circle(&mut self, center: Point, radius: f64) -> Result<(), VoronoiError> {
+        self.context.begin_path();
+        self.context.arc(center.x, center.y, radius, 0.0, std::f64::consts::PI * 2.0)?;
+        self.paint(true);
+        Ok(())
+    }
+
+    fn polyline(&mut self, points: &[Point]) -> Result<(), VoronoiError> {
+        self.context.begin_path();
+        for (i, p) in points.iter().enumerate() {
+            if i == 0 {
+                self.context.move_to(p.x, p.y);
+            } else {
+                self.context.line_to(p.x, p.y);
+            }
+        }
+        self.paint(false);
+        Ok(())
+    }
+
+    fn polygon(&mut self, points: &[Point]) -> Result<(), VoronoiError> {
+        self.context.begin_path();
+        for (i, p) in points.iter().enumerate() {
+            if i == 0 {
+                self.context.move_to(p.x, p.y);
+            } else {
+                self.context.line_to(p.x, p.y);
+            }
+        }
+        self.context.close_path();
+        self.paint(true);
+        Ok(())
+    }
+
+    /// Replaces the pixels under the image, like `putImageData`
+    fn image(&mut self, corner: Point, image: &Image) -> Result<(), VoronoiError> {
+        let data = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&image.pixels), image.width, image.height)?;
+        self.context.put_image_data(&data, corner.x.round(), corner.y.round())?;
+        Ok(())
+    }
+}
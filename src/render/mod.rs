@@ -0,0 +1,296 @@
+//!
+//! Drawing without caring what is drawn on. A `Renderer` knows how to draw a handful of shapes
+//! (circles, polylines, polygons and images) in the current style, and everything else is drawn
+//! from those, so the same code can draw on a canvas in the browser, into an SVG document, into an
+//! image in memory, or into a recording that tests can look through.
+//!
+//! Coordinates are the same as a canvas: y points down.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::dulaney::Triangulation;
+use crate::error::VoronoiError;
+use crate::image::Image;
+use crate::palette::{ACCENT, COLORS};
+use crate::point::Point;
+use crate::polygon::ConvexPolygon;
+use crate::voronoi::VoronoiDiagram;
+
+#[cfg(feature = "wasm")]
+pub mod canvas;
+pub mod recording;
+
+/// Something shapes can be drawn on. Shapes are drawn in the style most recently set, with their
+/// outlines stroked if the style has a stroke, and their insides filled if it has a fill.
+pub trait Renderer {
+    /// Erases everything drawn so far
+    fn clear(&mut self) -> Result<(), VoronoiError>;
+
+    /// Sets the style of the shapes drawn after this
+    fn style(&mut self, style: &Style) -> Result<(), VoronoiError>;
+
+    fn circle(&mut self, center: Point, radius: f64) -> Result<(), VoronoiError>;
+
+    /// An open chain of line segments, which is only ever stroked
+    fn polyline(&mut self, points: &[Point]) -> Result<(), VoronoiError>;
+
+    /// A closed polygon
+    fn polygon(&mut self, points: &[Point]) -> Result<(), VoronoiError>;
+
+    /// Copies an image onto the drawing, with its top left corner at the point, ignoring the style.
+    /// Canvases and images replace the pixels under it; SVG draws it over them.
+    fn image(&mut self, corner: Point, image: &Image) -> Result<(), VoronoiError>;
+
+    /// Starts a new named layer, for renderers that keep layers apart (like SVG groups). Others
+    /// can ignore it.
+    fn layer(&mut self, _name: &str) -> Result<(), VoronoiError> {
+        Ok(())
+    }
+}
+
+/// How shapes are drawn. Colors are CSS colors; renderers that aren't a browser understand hex
+/// colors ("#fab1a0"), `rgb(...)` and `rgba(...)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    /// The color of outlines, or None for no outline
+    pub stroke: Option<String>,
+    pub stroke_width: f64,
+
+    /// The color inside shapes, or None to leave them empty
+    pub fill: Option<String>,
+
+    /// From 0 (invisible) to 1 (opaque)
+    pub opacity: f64,
+
+    /// The lengths of the dashes and gaps of outlines, in turn, or empty for solid lines
+    pub dash: Vec<f64>,
+}
+
+impl Style {
+    /// Outlines only
+    pub fn stroke(color: &str, width: f64) -> Style {
+        Style {
+            stroke: Some(color.to_string()),
+            stroke_width: width,
+            fill: None,
+            opacity: 1.0,
+            dash: Vec::new(),
+        }
+    }
+
+    /// Filled shapes, without outlines
+    pub fn fill(color: &str) -> Style {
+        Style {
+            stroke: None,
+            stroke_width: 0.0,
+            fill: Some(color.to_string()),
+            opacity: 1.0,
+            dash: Vec::new(),
+        }
+    }
+}
+
+/// Which layers to draw for a diagram, and how. Layers that are None are left out. From the
+/// bottom up, they are the cells, bisectors, circumcircles, Delaunay edges, then sites.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagramStyles {
+    pub cells: Option<Style>,
+
+    /// Fills for the cells, cycled through, which replace the cells style's fill. Empty to use
+    /// the style's fill for every cell.
+    pub cell_fills: Vec<String>,
+
+    pub bisectors: Option<Style>,
+    pub circumcircles: Option<Style>,
+    pub delaunay: Option<Style>,
+    pub sites: Option<Style>,
+    pub site_radius: f64,
+}
+
+/// The look of the demos, with bisectors and circumcircles left out
+impl Default for DiagramStyles {
+    fn default() -> DiagramStyles {
+        DiagramStyles {
+            cells: Some(Style::stroke(ACCENT, 3.0)),
+            cell_fills: COLORS.iter().map(|color| color.to_string()).collect(),
+            bisectors: None,
+            circumcircles: None,
+            delaunay: Some(Style::stroke("#636e72", 1.0)),
+            sites: Some(Style::stroke(ACCENT, 4.0)),
+            site_radius: 6.0,
+        }
+    }
+}
+
+/// Draws a Voronoi diagram and the triangulation it came from, with the layers the styles ask
+/// for. Cells and bisectors are clipped to the region.
+pub fn diagram<R: Renderer + ?Sized>(renderer: &mut R, diagram: &VoronoiDiagram, region: &ConvexPolygon, styles: &DiagramStyles) -> Result<(), VoronoiError> {
+    if let Some(style) = &styles.cells {
+        cells(renderer, diagram, region, style, &styles.cell_fills)?;
+    }
+    if let Some(style) = &styles.bisectors {
+        bisectors(renderer, &diagram.triangulation, region, style)?;
+    }
+    if let Some(style) = &styles.circumcircles {
+        circumcircles(renderer, &diagram.triangulation, style)?;
+    }
+    if let Some(style) = &styles.delaunay {
+        delaunay(renderer, &diagram.triangulation, style)?;
+    }
+    if let Some(style) = &styles.sites {
+        sites(renderer, &diagram.sites, styles.site_radius, style)?;
+    }
+
+    Ok(())
+}
+
+/// A layer with a circle around each site
+pub fn sites<R: Renderer + ?Sized>(renderer: &mut R, sites: &[Point], radius: f64, style: &Style) -> Result<(), VoronoiError> {
+    renderer.layer("sites")?;
+    renderer.style(style)?;
+    for &site in sites {
+        renderer.circle(site, radius)?;
+    }
+
+    Ok(())
+}
+
+/// A layer with every edge of a triangulation
+pub fn delaunay<R: Renderer + ?Sized>(renderer: &mut R, triangulation: &Triangulation, style: &Style) -> Result<(), VoronoiError> {
+    renderer.layer("delaunay")?;
+    renderer.style(style)?;
+
+    let mesh = &triangulation.mesh;
+    for edge in mesh.edges() {
+        let (a, b) = (mesh.origin(edge), mesh.destination(edge));
+
+        // Each edge is two half-edges, so only draw one of them
+        if a < b {
+            renderer.polyline(&[mesh.point(a), mesh.point(b)])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A layer with each Voronoi cell, clipped to a region, and filled with the fills in turn
+pub fn cells<R: Renderer + ?Sized>(renderer: &mut R, diagram: &VoronoiDiagram, region: &ConvexPolygon, style: &Style, fills: &[String]) -> Result<(), VoronoiError> {
+    renderer.layer("cells")?;
+    renderer.style(style)?;
+
+    let mut style = style.clone();
+    for (site, cell) in diagram.clip(region).iter().enumerate() {
+        if cell.is_empty() {
+            continue;
+        }
+
+        if !fills.is_empty() {
+            style.fill = Some(fills[site % fills.len()].clone());
+            renderer.style(&style)?;
+        }
+        renderer.polygon(cell)?;
+    }
+
+    Ok(())
+}
+
+/// A layer with the whole perpendicular bisector of every Delaunay edge, clipped to a region.
+/// Each Voronoi edge is part of one of these lines.
+pub fn bisectors<R: Renderer + ?Sized>(renderer: &mut R, triangulation: &Triangulation, region: &ConvexPolygon, style: &Style) -> Result<(), VoronoiError> {
+    renderer.layer("bisectors")?;
+    renderer.style(style)?;
+
+    let mesh = &triangulation.mesh;
+    for edge in mesh.edges() {
+        let (a, b) = (mesh.origin(edge), mesh.destination(edge));
+        if a >= b {
+            continue;
+        }
+
+        let (p, q) = (mesh.point(a), mesh.point(b));
+        let middle = Point::new((p.x + q.x) / 2.0, (p.y + q.y) / 2.0);
+        let (dx, dy) = (q.x - p.x, q.y - p.y);
+        let length = dx.hypot(dy);
+
+        // Long enough to cross the region from wherever the middle is
+        let reach = region
+            .vertices()
+            .iter()
+            .map(|v| (v.x - middle.x).hypot(v.y - middle.y))
+            .fold(0.0, f64::max)
+            / length;
+        let line = [
+            Point::new(middle.x + dy * reach, middle.y - dx * reach),
+            Point::new(middle.x - dy * reach, middle.y + dx * reach),
+        ];
+
+        for piece in region.clip_polyline(&line) {
+            renderer.polyline(&piece)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A layer with the circumcircle of every triangle
+pub fn circumcircles<R: Renderer + ?Sized>(renderer: &mut R, triangulation: &Triangulation, style: &Style) -> Result<(), VoronoiError> {
+    renderer.layer("circumcircles")?;
+    renderer.style(style)?;
+
+    for triangle in triangulation.triangles.iter() {
+        renderer.circle(triangle.circumcenter, triangle.circumradius)?;
+    }
+
+    Ok(())
+}
+
+/// Splits a polyline into the pieces a dash pattern draws, for renderers without dashes of their
+/// own. Like a canvas, a pattern with an odd number of lengths is repeated to make it even, and a
+/// pattern that is empty or adds up to nothing draws a solid line.
+pub fn dashes(points: &[Point], pattern: &[f64]) -> Vec<Vec<Point>> {
+    let total: f64 = pattern.iter().sum();
+    if pattern.iter().any(|&length| !length.is_finite() || length < 0.0) || total <= 0.0 || points.len() < 2 {
+        return vec![points.to_vec()];
+    }
+
+    let pattern: Vec<f64> = if pattern.len() % 2 == 1 { pattern.iter().chain(pattern).copied().collect() } else { pattern.to_vec() };
+    let (mut dash, mut left) = (0, pattern[0]);
+    let mut pieces = Vec::new();
+    let mut piece = vec![points[0]];
+
+    for segment in points.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let length = (b.x - a.x).hypot(b.y - a.y);
+        let mut along = 0.0;
+
+        // Step through every change between dash and gap inside this segment
+        while length - along > left {
+            along += left;
+            let t = along / length;
+            let p = Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t);
+
+            // A dash ends here, or one starts
+            if dash % 2 == 0 {
+                piece.push(p);
+                pieces.push(std::mem::take(&mut piece));
+            } else {
+                piece = vec![p];
+            }
+
+            dash = (dash + 1) % pattern.len();
+            left = pattern[dash];
+        }
+
+        left -= length - along;
+        if dash % 2 == 0 {
+            piece.push(b);
+        }
+    }
+
+    if dash % 2 == 0 && piece.len() >= 2 {
+        pieces.push(piece);
+    }
+
+    pieces
+}
@@ -0,0 +1,310 @@
+//!
+//! A renderer that draws nothing, and instead keeps a list of everything it was asked to do, so
+//! drawing code can be checked without a browser.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::error::VoronoiError;
+use crate::image::Image;
+use crate::point::Point;
+use super::{Renderer, Style};
+
+/// One call made to a renderer
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Clear,
+    Style(Style),
+    Layer(String),
+    Circle { center: Point, radius: f64 },
+    Polyline(Vec<Point>),
+    Polygon(Vec<Point>),
+    Image { corner: Point, image: Image },
+}
+
+/// The calls made to it, in order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub commands: Vec<Command>,
+}
+
+impl Recording {
+    pub fn new() -> Recording {
+        Recording::default()
+    }
+
+    /// The shapes drawn (circles, polylines, polygons and images), leaving out clears, styles and
+    /// layers
+    pub fn shapes(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter().filter(|command| {
+            matches!(command, Command::Circle { .. } | Command::Polyline(_) | Command::Polygon(_) | Command::Image { .. })
+        })
+    }
+}
+
+impl Renderer for Recording {
+    fn clear(&mut self) -> Result<(), VoronoiError> {
+        self.commands.push(Command::Clear);
+        Ok(())
+    }
+
+    fn style(&mut self, style: &Style) -> Result<(), VoronoiError> {
+        self.commands.push(Command::Style(style.clone()));
+        Ok(())
+    }
+
+    fn circle(&mut self, center: Point, radius: f64) -> Result<(), VoronoiError> {
+        self.commands.push(Command::Circle { center, radius });
+        Ok(())
+    }
+
+    fn polyline(&mut self, points: &[Point]) -> Result<(), VoronoiError> {
+        self.commands.push(Command::Polyline(points.to_vec()));
+        Ok(())
+    }
+
+    fn polygon(&mut self, points: &[Point]) -> Result<(), VoronoiError> {
+        self.commands.push(Command::Polygon(points.to_vec()));
+        Ok(())
+    }
+
+    fn image(&mut self, corner: Point, image: &Image) -> Result<(), VoronoiError> {
+        self.commands.push(Command::Image { corner, image: image.clone() });
+        Ok(())
+    }
+
+    fn layer(&mut self, name: &str) -> Result<(), VoronoiError> {
+        self.commands.push(Command::Layer(name.to_string()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demos::cursor_point::draw_nearest;
+    use crate::demos::naive::draw_raster;
+    use crate::demos::perpendicular_bisector::draw_bisectors;
+    use crate::demos::draw_sites;
+    use crate::palette::{parse_color, ACCENT, COLORS};
+    use crate::polygon::{contains, ConvexPolygon};
+    use crate::raster::RasterVoronoi;
+    use crate::render::{self, DiagramStyles};
+    use crate::voronoi::VoronoiDiagram;
+
+    /// Three sites around a fourth, which makes three triangles with six edges
+    fn sites() -> Vec<Point> {
+        vec![Point::new(20.0, 20.0), Point::new(80.0, 20.0), Point::new(50.0, 80.0), Point::new(50.0, 45.0)]
+    }
+
+    fn distance(p: Point, q: Point) -> f64 {
+        (p.x - q.x).hypot(p.y - q.y)
+    }
+
+    #[test]
+    fn diagram() {
+        let sites = sites();
+        let diagram = VoronoiDiagram::new(&sites).unwrap();
+        let region = ConvexPolygon::rectangle(0.0, 0.0, 100.0, 100.0).unwrap();
+        let styles = DiagramStyles::default();
+
+        let mut recording = Recording::new();
+        render::diagram(&mut recording, &diagram, &region, &styles).unwrap();
+        let commands = &recording.commands;
+
+        // Each cell is styled with its own fill, then there are six Delaunay edges and four sites
+        assert_eq!(commands.len(), 2 + 2 * 4 + 2 + 6 + 2 + 4);
+        assert_eq!(recording.shapes().count(), 4 + 6 + 4);
+
+        assert_eq!(commands[0], Command::Layer("cells".to_string()));
+        assert_eq!(commands[1], Command::Style(Style::stroke(ACCENT, 3.0)));
+        for (site, pair) in commands[2..10].chunks(2).enumerate() {
+            let mut style = Style::stroke(ACCENT, 3.0);
+            style.fill = Some(COLORS[site].to_string());
+            assert_eq!(pair[0], Command::Style(style));
+
+            // The cell holds its site, and every corner is inside the region
+            match &pair[1] {
+                Command::Polygon(cell) => {
+                    assert!(contains(cell, sites[site]));
+                    assert!(cell.iter().all(|p| p.x >= -1e-9 && p.x <= 100.0 + 1e-9 && p.y >= -1e-9 && p.y <= 100.0 + 1e-9));
+                }
+                other => panic!("expected a cell, got {:?}", other),
+            }
+        }
+
+        assert_eq!(commands[10], Command::Layer("delaunay".to_string()));
+        assert_eq!(commands[11], Command::Style(Style::stroke("#636e72", 1.0)));
+        let mut edges: Vec<(usize, usize)> = commands[12..18]
+            .iter()
+            .map(|command| match command {
+                Command::Polyline(line) => {
+                    assert_eq!(line.len(), 2);
+                    let index = |p: Point| sites.iter().position(|&site| site == p).unwrap();
+                    let (a, b) = (index(line[0]), index(line[1]));
+                    (a.min(b), a.max(b))
+                }
+                other => panic!("expected an edge, got {:?}", other),
+            })
+            .collect();
+        edges.sort_unstable();
+        assert_eq!(edges, vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]);
+
+        assert_eq!(commands[18], Command::Layer("sites".to_string()));
+        assert_eq!(commands[19], Command::Style(Style::stroke(ACCENT, 4.0)));
+        for (command, &site) in commands[20..].iter().zip(sites.iter()) {
+            assert_eq!(*command, Command::Circle { center: site, radius: 6.0 });
+        }
+    }
+
+    #[test]
+    fn diagram_with_every_layer() {
+        let diagram = VoronoiDiagram::new(&sites()).unwrap();
+        let region = ConvexPolygon::rectangle(0.0, 0.0, 100.0, 100.0).unwrap();
+        let styles = DiagramStyles {
+            cell_fills: Vec::new(),
+            bisectors: Some(Style::stroke("#0000ff", 1.0)),
+            circumcircles: Some(Style::stroke("#ff0000", 1.0)),
+            ..DiagramStyles::default()
+        };
+
+        let mut recording = Recording::new();
+        render::diagram(&mut recording, &diagram, &region, &styles).unwrap();
+
+        let layers: Vec<&str> = recording
+            .commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::Layer(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(layers, ["cells", "bisectors", "circumcircles", "delaunay", "sites"]);
+
+        // Without fills, the cells are only styled once
+        let styles = recording.commands.iter().filter(|command| matches!(command, Command::Style(_))).count();
+        assert_eq!(styles, 5);
+
+        // Each bisector is clipped to the region, and its ends are as far from two of the sites
+        let start = recording.commands.iter().position(|command| *command == Command::Layer("bisectors".to_string())).unwrap();
+        let bisectors: Vec<&Vec<Point>> = recording.commands[start + 2..start + 8]
+            .iter()
+            .map(|command| match command {
+                Command::Polyline(line) => line,
+                other => panic!("expected a bisector, got {:?}", other),
+            })
+            .collect();
+        for line in bisectors {
+            for &p in line {
+                assert!(p.x.min(p.y) > -1e-9 && p.x.max(p.y) < 100.0 + 1e-9);
+                let mut distances: Vec<f64> = sites().iter().map(|&site| distance(p, site)).collect();
+                distances.sort_by(f64::total_cmp);
+                assert!(distances.windows(2).any(|pair| pair[1] - pair[0] < 1e-9));
+            }
+        }
+
+        // One circumcircle for each triangle, through its corners
+        let circles: Vec<(Point, f64)> = recording
+            .shapes()
+            .filter_map(|command| match *command {
+                Command::Circle { center, radius } if radius != 6.0 => Some((center, radius)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(circles.len(), 3);
+        for (center, radius) in circles {
+            let on_circle = sites().iter().filter(|&&site| (distance(center, site) - radius).abs() < 1e-9).count();
+            assert_eq!(on_circle, 3);
+        }
+    }
+
+    #[test]
+    fn demo_sites() {
+        let sites = sites();
+        let mut recording = Recording::new();
+        draw_sites(&mut recording, &sites).unwrap();
+
+        let mut expected = vec![Command::Layer("sites".to_string()), Command::Style(Style::stroke(ACCENT, 4.0))];
+        expected.extend(sites.iter().map(|&center| Command::Circle { center, radius: 6.0 }));
+        assert_eq!(recording.commands, expected);
+    }
+
+    #[test]
+    fn demo_nearest() {
+        let sites = sites();
+        let cursor = Point::new(55.0, 70.0);
+        let mut recording = Recording::new();
+        draw_nearest(&mut recording, &sites, cursor).unwrap();
+
+        // The sites, then a line from the cursor's nearest site to it
+        assert_eq!(recording.commands.len(), 2 + sites.len() + 1);
+        assert_eq!(recording.shapes().count(), sites.len() + 1);
+        assert_eq!(recording.commands.last(), Some(&Command::Polyline(vec![sites[2], cursor])));
+
+        let mut empty = Recording::new();
+        draw_nearest(&mut empty, &[], cursor).unwrap();
+        assert_eq!(empty.shapes().count(), 0);
+    }
+
+    #[test]
+    fn demo_bisectors() {
+        // Sites at the same height would have a vertical bisector, which the demo can't draw
+        let sites = [Point::new(20.0, 22.0), Point::new(80.0, 18.0), Point::new(50.0, 80.0), Point::new(47.0, 45.0)];
+        let width = 100.0;
+        let mut recording = Recording::new();
+        draw_bisectors(&mut recording, &sites, width).unwrap();
+
+        // A faint line for every ordered pair of sites
+        assert_eq!(recording.commands.len(), 1 + sites.len() * (sites.len() - 1));
+        assert_eq!(recording.commands[0], Command::Style(Style::stroke("rgba(250, 177, 160, 0.1)", 3.0)));
+
+        let mut lines = recording.commands[1..].iter();
+        for (i, &a) in sites.iter().enumerate() {
+            for (j, &b) in sites.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+
+                // Across the canvas, with both ends as far from a as from b
+                match lines.next() {
+                    Some(Command::Polyline(line)) => {
+                        assert_eq!(line.len(), 2);
+                        assert_eq!((line[0].x, line[1].x), (0.0, width - 1.0));
+                        for &p in line {
+                            assert!((distance(p, a) - distance(p, b)).abs() < 1e-9 * distance(p, a).max(1.0));
+                        }
+                    }
+                    other => panic!("expected a bisector, got {:?}", other),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn demo_raster() {
+        let sites = [Point::new(1.0, 1.0), Point::new(6.0, 4.0)];
+        let raster = RasterVoronoi::new(8, 6, sites.len(), |i, p| distance(sites[i], p));
+        let mut recording = Recording::new();
+        draw_raster(&mut recording, &raster, &sites).unwrap();
+
+        // The pixels, then the sites as small dark circles
+        assert_eq!(recording.commands.len(), 1 + 2 + sites.len());
+        match &recording.commands[0] {
+            Command::Image { corner, image } => {
+                assert_eq!(*corner, Point::new(0.0, 0.0));
+                assert_eq!((image.width, image.height), (8, 6));
+
+                // Each pixel has the color of its nearest site
+                let pixel = |x: usize, y: usize| &image.pixels[(y * 8 + x) * 4..(y * 8 + x) * 4 + 4];
+                assert_eq!(pixel(0, 0), parse_color(COLORS[0]).unwrap());
+                assert_eq!(pixel(7, 5), parse_color(COLORS[1]).unwrap());
+                assert_eq!(pixel(7, 5), [0x81, 0xec, 0xec, 255]);
+            }
+            other => panic!("expected an image, got {:?}", other),
+        }
+        assert_eq!(recording.commands[1], Command::Layer("sites".to_string()));
+        assert_eq!(recording.commands[2], Command::Style(Style::stroke("#333333", 4.0)));
+        assert_eq!(recording.commands[3], Command::Circle { center: sites[0], radius: 2.0 });
+        assert_eq!(recording.commands[4], Command::Circle { center: sites[1], radius: 2.0 });
+    }
+}
@@ -0,0 +1,327 @@
+//!
+//! Writes triangulations and Voronoi diagrams as SVG, for print and design tools. Each kind of
+//! thing (sites, Delaunay edges, Voronoi cells, bisectors and circumcircles) goes in its own layer,
+//! a `<g>` group with its own stroke and fill, so the layers can be restyled, hidden or moved
+//! about after export. Nothing here needs a browser.
+//!
+//! Coordinates are written as they are, so like a canvas, y points down the page.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use std::fmt::{self, Display, Write};
+use base64::Engine;
+use crate::error::VoronoiError;
+use crate::image::Image;
+use crate::point::Point;
+use crate::polygon::ConvexPolygon;
+use crate::render::{self, DiagramStyles, Renderer, Style};
+use crate::voronoi::VoronoiDiagram;
+
+/// An SVG document, drawn into like any other renderer. Each layer becomes a group, styled the way
+/// its first shape is drawn; shapes drawn in another style carry the difference as attributes of
+/// their own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Svg {
+    width: f64,
+    height: f64,
+    body: String,
+
+    /// The style shapes are drawn in
+    style: Style,
+
+    /// The id of the next group, if a layer has been started but nothing drawn in it yet
+    pending: Option<String>,
+
+    /// The style of the open group, if there is one
+    group: Option<Style>,
+}
+
+impl Svg {
+    /// An empty document showing the region from (0, 0) to (width, height)
+    pub fn new(width: f64, height: f64) -> Svg {
+        Svg {
+            width,
+            height,
+            body: String::new(),
+            style: Style::stroke("#000000", 1.0),
+            pending: None,
+            group: None,
+        }
+    }
+
+    /// Writes a shape element, with the parts of the style its group doesn't give
+    fn shape(&mut self, element: &str, attributes: &str) {
+        let group = self.open_group();
+        let _ = writeln!(self.body, "    <{} {}{}/>", element, attributes, style_attributes(&self.style, Some(&group)));
+    }
+
+    /// Opens a group in the current style, unless one is open already, and returns its style
+    fn open_group(&mut self) -> Style {
+        if let Some(group) = &self.group {
+            return group.clone();
+        }
+
+        let id = self.pending.take().map(|id| format!(r#" id="{}""#, escape(&id))).unwrap_or_default();
+        let _ = writeln!(self.body, "  <g{}{}>", id, style_attributes(&self.style, None));
+        self.group = Some(self.style.clone());
+        self.style.clone()
+    }
+
+    fn end_group(&mut self) {
+        if self.group.take().is_some() {
+            self.body.push_str("  </g>\n");
+        }
+    }
+}
+
+impl Renderer for Svg {
+    fn clear(&mut self) -> Result<(), VoronoiError> {
+        self.body.clear();
+        self.pending = None;
+        self.group = None;
+        Ok(())
+    }
+
+    fn style(&mut self, style: &Style) -> Result<(), VoronoiError> {
+        self.style = style.clone();
+        Ok(())
+    }
+
+    fn circle(&mut self, center: Point, radius: f64) -> Result<(), VoronoiError> {
+        self.shape("circle", &format!(r#"cx="{}" cy="{}" r="{}""#, center.x, center.y, radius));
+        Ok(())
+    }
+
+    fn polyline(&mut self, points: &[Point]) -> Result<(), VoronoiError> {
+        // A polyline is never filled, even when the style has a fill
+        let fill = self.style.fill.take();
+        self.shape("polyline", &format!(r#"points="{}""#, coordinates(points)));
+        self.style.fill = fill;
+        Ok(())
+    }
+
+    fn polygon(&mut self, points: &[Point]) -> Result<(), VoronoiError> {
+        self.shape("polygon", &format!(r#"points="{}""#, coordinates(points)));
+        Ok(())
+    }
+
+    /// Embeds the image as a PNG
+    fn image(&mut self, corner: Point, image: &Image) -> Result<(), VoronoiError> {
+        let mut png = Vec::new();
+        image.write_png(&mut png).map_err(|error| VoronoiError::Render(error.to_string()))?;
+
+        let attributes = format!(
+            r#"x="{}" y="{}" width="{}" height="{}" href="data:image/png;base64,{}""#,
+            corner.x.round(),
+            corner.y.round(),
+            image.width,
+            image.height,
+            base64::engine::general_purpose::STANDARD.encode(png),
+        );
+        self.open_group();
+        let _ = writeln!(self.body, "    <image {}/>", attributes);
+        Ok(())
+    }
+
+    /// Ends the open group, and gives the next one an id, so that it can be found in other tools
+    fn layer(&mut self, name: &str) -> Result<(), VoronoiError> {
+        self.end_group();
+        self.pending = Some(name.to_string());
+        Ok(())
+    }
+}
+
+impl Display for Svg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            self.width, self.height
+        )?;
+        f.write_str(&self.body)?;
+        if self.group.is_some() {
+            f.write_str("  </g>\n")?;
+        }
+        writeln!(f, "</svg>")
+    }
+}
+
+/// Draws a Voronoi diagram, and the triangulation it came from, on a `width` by `height` page.
+/// Cells and bisectors are clipped to the page. Fails if the page has no area.
+pub fn diagram_svg(diagram: &VoronoiDiagram, width: f64, height: f64, styles: &DiagramStyles) -> Result<String, VoronoiError> {
+    let region = ConvexPolygon::rectangle(0.0, 0.0, width, height)?;
+    let mut svg = Svg::new(width, height);
+    render::diagram(&mut svg, diagram, &region, styles)?;
+
+    Ok(svg.to_string())
+}
+
+/// The presentation attributes for a style, leaving out any that a group in the `inherited` style
+/// already gives. Opacity is left to each shape, since a group's opacity would multiply with it.
+fn style_attributes(style: &Style, inherited: Option<&Style>) -> String {
+    let none = "none".to_string();
+    let mut attributes = String::new();
+
+    if inherited.map(|inherited| &inherited.stroke) != Some(&style.stroke) {
+        let _ = write!(attributes, r#" stroke="{}""#, escape(style.stroke.as_ref().unwrap_or(&none)));
+    }
+    if inherited.map(|inherited| inherited.stroke_width) != Some(style.stroke_width) {
+        let _ = write!(attributes, r#" stroke-width="{}""#, style.stroke_width);
+    }
+    if inherited.map(|inherited| &inherited.fill) != Some(&style.fill) {
+        let _ = write!(attributes, r#" fill="{}""#, escape(style.fill.as_ref().unwrap_or(&none)));
+    }
+    if inherited.map_or(!style.dash.is_empty(), |inherited| inherited.dash != style.dash) {
+        let dash: Vec<String> = style.dash.iter().map(|length| length.to_string()).collect();
+        let dash = if dash.is_empty() { none } else { dash.join(" ") };
+        let _ = write!(attributes, r#" stroke-dasharray="{}""#, dash);
+    }
+    if inherited.is_some() && style.opacity != 1.0 {
+        let _ = write!(attributes, r#" opacity="{}""#, style.opacity);
+    }
+
+    attributes
+}
+
+/// Points as an SVG points list, "x1,y1 x2,y2 ..."
+fn coordinates(points: &[Point]) -> String {
+    let mut list = String::with_capacity(points.len() * 16);
+    for (i, p) in points.iter().enumerate() {
+        if i > 0 {
+            list.push(' ');
+        }
+        let _ = write!(list, "{},{}", p.x, p.y);
+    }
+
+    list
+}
+
+/// Escapes text for an attribute value
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that every tag is closed in the right order and has its quotes paired up, and returns
+    /// the name of every element
+    fn elements(svg: &str) -> Vec<String> {
+        let mut open: Vec<String> = Vec::new();
+        let mut names = Vec::new();
+
+        let mut rest = svg;
+        while let Some(start) = rest.find('<') {
+            let end = start + rest[start..].find('>').expect("a tag is never closed");
+            let tag = &rest[start + 1..end];
+            assert_eq!(tag.matches('"').count() % 2, 0, "<{}> has an unpaired quote", tag);
+            assert!(!tag[1..].contains('<'), "<{}> has a < inside it", tag);
+
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop().as_deref(), Some(name), "</{}> closes the wrong element", name);
+            } else {
+                let name = tag.split(|c: char| c.is_whitespace() || c == '/').next().unwrap().to_string();
+                if !tag.ends_with('/') {
+                    open.push(name.clone());
+                }
+                names.push(name);
+            }
+
+            rest = &rest[end + 1..];
+        }
+        assert!(open.is_empty(), "{:?} are never closed", open);
+
+        names
+    }
+
+    fn count(names: &[String], element: &str) -> usize {
+        names.iter().filter(|name| *name == element).count()
+    }
+
+    #[test]
+    fn diagram() {
+        let sites = [
+            Point::new(20.0, 20.0),
+            Point::new(75.0, 15.0),
+            Point::new(30.0, 65.0),
+            Point::new(85.0, 60.0),
+            Point::new(50.0, 40.0),
+        ];
+        let diagram = VoronoiDiagram::new(&sites).unwrap();
+        let styles = DiagramStyles {
+            circumcircles: Some(Style::stroke("#000", 1.0)),
+            ..DiagramStyles::default()
+        };
+
+        let svg = diagram_svg(&diagram, 100.0, 80.0, &styles).unwrap();
+        let names = elements(&svg);
+
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="80" viewBox="0 0 100 80">"#));
+        assert_eq!(count(&names, "svg"), 1);
+        assert_eq!(count(&names, "g"), 4);
+        for layer in ["cells", "circumcircles", "delaunay", "sites"].iter() {
+            assert!(svg.contains(&format!(r#"<g id="{}""#, layer)));
+        }
+
+        // Every site is inside the page, so every cell is drawn
+        let triangles = diagram.triangulation.triangles.len();
+        let edges = diagram.triangulation.mesh.edge_count() / 2;
+        assert_eq!(count(&names, "polygon"), sites.len());
+        assert_eq!(count(&names, "circle"), sites.len() + triangles);
+        assert_eq!(count(&names, "polyline"), edges);
+    }
+
+    #[test]
+    fn shapes_inherit_their_group_style() {
+        let mut svg = Svg::new(10.0, 10.0);
+        svg.layer("dots").unwrap();
+        svg.style(&Style::stroke("red", 2.0)).unwrap();
+        svg.circle(Point::new(1.0, 1.0), 1.0).unwrap();
+        svg.circle(Point::new(2.0, 2.0), 1.0).unwrap();
+
+        // Only what differs from the group is written on the shape
+        svg.style(&Style::stroke("blue", 2.0)).unwrap();
+        svg.circle(Point::new(3.0, 3.0), 1.0).unwrap();
+        let faded = Style { opacity: 0.5, ..Style::stroke("red", 2.0) };
+        svg.style(&faded).unwrap();
+        svg.circle(Point::new(4.0, 4.0), 1.0).unwrap();
+
+        svg.layer("fills").unwrap();
+        svg.style(&Style::fill("green")).unwrap();
+        svg.polygon(&[Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(0.0, 1.0)]).unwrap();
+        svg.polyline(&[Point::new(0.0, 0.0), Point::new(1.0, 1.0)]).unwrap();
+
+        let svg = svg.to_string();
+        elements(&svg);
+        let lines: Vec<&str> = svg.lines().collect();
+
+        assert_eq!(lines[1], r#"  <g id="dots" stroke="red" stroke-width="2" fill="none">"#);
+        assert_eq!(lines[2], r#"    <circle cx="1" cy="1" r="1"/>"#);
+        assert_eq!(lines[3], r#"    <circle cx="2" cy="2" r="1"/>"#);
+        assert_eq!(lines[4], r#"    <circle cx="3" cy="3" r="1" stroke="blue"/>"#);
+        assert_eq!(lines[5], r#"    <circle cx="4" cy="4" r="1" opacity="0.5"/>"#);
+        assert_eq!(lines[6], "  </g>");
+        assert_eq!(lines[7], r#"  <g id="fills" stroke="none" stroke-width="0" fill="green">"#);
+        assert_eq!(lines[8], r#"    <polygon points="0,0 1,0 0,1"/>"#);
+        assert_eq!(lines[9], r#"    <polyline points="0,0 1,1" fill="none"/>"#);
+        assert_eq!(lines[10], "  </g>");
+        assert_eq!(lines[11], "</svg>");
+    }
+
+    #[test]
+    fn dashes_and_escaping() {
+        let mut svg = Svg::new(10.0, 10.0);
+        svg.layer(r#"a "quoted" <layer>"#).unwrap();
+        svg.style(&Style { dash: vec![2.0, 1.0], ..Style::stroke("black", 1.0) }).unwrap();
+        svg.polyline(&[Point::new(0.0, 0.0), Point::new(5.0, 5.0)]).unwrap();
+        svg.style(&Style::stroke("black", 1.0)).unwrap();
+        svg.polyline(&[Point::new(5.0, 0.0), Point::new(0.0, 5.0)]).unwrap();
+
+        let svg = svg.to_string();
+        elements(&svg);
+        assert!(svg.contains(r#"<g id="a &quot;quoted&quot; &lt;layer&gt;" stroke="black" stroke-width="1" fill="none" stroke-dasharray="2 1">"#));
+        assert!(svg.contains(r#"<polyline points="5,0 0,5" stroke-dasharray="none"/>"#));
+    }
+}
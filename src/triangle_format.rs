@@ -0,0 +1,703 @@
+//!
+//! Reads and writes the file formats of Jonathan Shewchuk's Triangle, so triangulations can be
+//! compared with its output, and passed to (or taken from) finite element tools that use them:
+//!
+//! - `.node`: vertices, each with any number of attributes and an optional boundary marker
+//! - `.ele`: triangles, as the numbers of their three corners, with any number of attributes
+//! - `.poly`: a planar straight line graph, with vertices, segments, holes and regions
+//! - `.edge`: edges, with an optional boundary marker
+//! - `.v.node` and `.v.edge`: the Voronoi diagram, in the same formats, where an edge can be a ray
+//!
+//! Each file starts with a line of counts. Everything after a `#` is a comment, and blank lines
+//! are ignored. Vertices are numbered from 0 or 1, whichever the first vertex in the `.node` file
+//! uses, and the other files number them the same way. Here, every index is from 0, and `first`
+//! only matters when reading and writing.
+//!
+//! See https://www.cs.cmu.edu/~quake/triangle.html for the details.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use std::fmt::Write;
+use crate::dulaney::Triangulation;
+use crate::error::VoronoiError;
+use crate::point::Point;
+
+/// Vertices, from a `.node` or `.v.node` file, or the start of a `.poly` file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Nodes {
+    pub points: Vec<Point>,
+
+    /// The attributes of each vertex. Every vertex has the same number of them.
+    pub attributes: Vec<Vec<f64>>,
+
+    /// The boundary marker of each vertex, if there are any
+    pub markers: Option<Vec<i32>>,
+
+    /// The number of the first vertex in the files, 0 or 1
+    pub first: usize,
+}
+
+/// Triangles, from an `.ele` file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Elements {
+    /// The indices of the corners of each triangle
+    pub triangles: Vec<[usize; 3]>,
+
+    /// The attributes of each triangle. Every triangle has the same number of them.
+    pub attributes: Vec<Vec<f64>>,
+}
+
+/// Edges between vertices, from an `.edge` file or the segments of a `.poly` file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Edges {
+    /// The indices of the ends of each edge
+    pub edges: Vec<[usize; 2]>,
+
+    /// The boundary marker of each edge, if there are any
+    pub markers: Option<Vec<i32>>,
+}
+
+/// A region of a `.poly` file, which gives the triangles around a point an attribute, and a limit
+/// on their area (negative for no limit)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    pub point: Point,
+    pub attribute: f64,
+    pub max_area: f64,
+}
+
+/// A planar straight line graph, from a `.poly` file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Poly {
+    pub nodes: Nodes,
+    pub segments: Edges,
+
+    /// A point inside each hole, from which triangles are eaten away up to the segments
+    pub holes: Vec<Point>,
+
+    pub regions: Vec<Region>,
+}
+
+/// An edge of a Voronoi diagram, from a `.v.edge` file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VoronoiEdge {
+    /// An edge between two Voronoi vertices
+    Segment(usize, usize),
+
+    /// An edge going out to infinity from a Voronoi vertex, in a direction
+    Ray(usize, Point),
+}
+
+impl Nodes {
+    /// The points of a triangulation, numbered from 1 like Triangle does, with no attributes.
+    /// Points on the convex hull have a boundary marker of 1, and the others 0.
+    pub fn from_triangulation(triangulation: &Triangulation) -> Nodes {
+        let mesh = &triangulation.mesh;
+
+        Nodes {
+            points: triangulation.points().collect(),
+            attributes: vec![Vec::new(); mesh.vertex_count()],
+            markers: Some(mesh.vertices().map(|vertex| mesh.is_boundary_vertex(vertex) as i32).collect()),
+            first: 1,
+        }
+    }
+}
+
+impl Elements {
+    /// The triangles of a triangulation, with their corners counter-clockwise, and no attributes
+    pub fn from_triangulation(triangulation: &Triangulation) -> Elements {
+        let mesh = &triangulation.mesh;
+        let triangles: Vec<[usize; 3]> = mesh
+            .faces()
+            .map(|face| {
+                let mut corners = mesh.face_vertices(face).map(|vertex| vertex.0);
+                [corners.next().unwrap_or(0), corners.next().unwrap_or(0), corners.next().unwrap_or(0)]
+            })
+            .collect();
+
+        Elements {
+            attributes: vec![Vec::new(); triangles.len()],
+            triangles,
+        }
+    }
+
+    /// Builds the triangulation these triangles make of the vertices. Fails the same way as
+    /// `Triangulation::from_triangles`.
+    pub fn to_triangulation(&self, nodes: &Nodes) -> Result<Triangulation, VoronoiError> {
+        Triangulation::from_triangles(&nodes.points, &self.triangles)
+    }
+}
+
+impl Edges {
+    /// Every edge of a triangulation. Edges on the convex hull have a boundary marker of 1, and
+    /// the others 0.
+    pub fn from_triangulation(triangulation: &Triangulation) -> Edges {
+        let mesh = &triangulation.mesh;
+        let mut edges = Edges {
+            edges: Vec::new(),
+            markers: Some(Vec::new()),
+        };
+
+        for edge in mesh.edges() {
+            let (a, b) = (mesh.origin(edge), mesh.destination(edge));
+
+            // Each edge is two half-edges, so only write one of them
+            if a < b {
+                edges.edges.push([a.0, b.0]);
+                if let Some(markers) = edges.markers.as_mut() {
+                    markers.push(mesh.is_boundary_edge(edge) as i32);
+                }
+            }
+        }
+
+        edges
+    }
+}
+
+/// The Voronoi diagram of a triangulation, the way Triangle writes it with `-v`: a Voronoi vertex
+/// at the circumcenter of each triangle, in the same order as the triangles, and an edge across
+/// each edge of the triangulation. Edges of the convex hull become rays, pointing away from the
+/// triangulation.
+pub fn voronoi_from_triangulation(triangulation: &Triangulation) -> (Nodes, Vec<VoronoiEdge>) {
+    let mesh = &triangulation.mesh;
+    let nodes = Nodes {
+        points: triangulation.triangles.iter().map(|triangle| triangle.circumcenter).collect(),
+        attributes: vec![Vec::new(); triangulation.triangles.len()],
+        markers: None,
+        first: 1,
+    };
+
+    let mut edges = Vec::new();
+    for edge in mesh.edges() {
+        let twin = mesh.twin(edge);
+        let (a, b) = (mesh.origin(edge), mesh.destination(edge));
+
+        match (mesh.face(edge), mesh.face(twin)) {
+            (Some(left), Some(right)) if a < b => edges.push(VoronoiEdge::Segment(left.0, right.0)),
+
+            // The triangle is on the left of the edge, so the outside is on its right
+            (Some(face), None) => {
+                let (p, q) = (mesh.point(a), mesh.point(b));
+                edges.push(VoronoiEdge::Ray(face.0, Point::new(q.y - p.y, p.x - q.x)));
+            }
+            _ => {}
+        }
+    }
+
+    (nodes, edges)
+}
+
+/// Reads a `.node` (or `.v.node`) file
+pub fn read_node(text: &str) -> Result<Nodes, VoronoiError> {
+    let mut lines = Lines::new(text);
+    let nodes = read_vertices(&mut lines)?;
+    lines.end()?;
+
+    Ok(nodes)
+}
+
+/// Writes a `.node` (or `.v.node`) file
+pub fn write_node(nodes: &Nodes) -> String {
+    let mut text = String::new();
+    write_vertices(&mut text, nodes);
+
+    text
+}
+
+/// Reads an `.ele` file, for the vertices in a `.node` file. Second order triangles (with 6
+/// corners) are read as their first 3 corners, which are their vertices.
+pub fn read_ele(text: &str, nodes: &Nodes) -> Result<Elements, VoronoiError> {
+    let mut lines = Lines::new(text);
+    let header = lines.next_line()?;
+    let count = header.usize(0)?;
+    let corners = header.optional_usize(1, 3)?;
+    let attributes = header.optional_usize(2, 0)?;
+    if corners < 3 {
+        return Err(header.error(&format!("triangles can't have {} corners", corners)));
+    }
+
+    let mut elements = Elements::default();
+    for _ in 0..count {
+        let line = lines.next_line()?;
+        let vertex = |i: usize| line.vertex(1 + i, nodes);
+
+        elements.triangles.push([vertex(0)?, vertex(1)?, vertex(2)?]);
+        elements.attributes.push((0..attributes).map(|i| line.f64(corners.saturating_add(1 + i))).collect::<Result<_, _>>()?);
+    }
+    lines.end()?;
+
+    Ok(elements)
+}
+
+/// Writes an `.ele` file, numbering vertices from `first`
+pub fn write_ele(elements: &Elements, first: usize) -> String {
+    let attributes = elements.attributes.first().map_or(0, Vec::len);
+    let mut text = format!("{}  3  {}\n", elements.triangles.len(), attributes);
+
+    for (i, triangle) in elements.triangles.iter().enumerate() {
+        let _ = write!(text, "{:4}    {}  {}  {}", i + first, triangle[0] + first, triangle[1] + first, triangle[2] + first);
+        for attribute in elements.attributes.get(i).into_iter().flatten() {
+            let _ = write!(text, "  {}", attribute);
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Reads a `.poly` file. A `.poly` file can leave its vertices to a `.node` file, by saying it has
+/// none, in which case they are taken from `nodes`. Fails if it does this, and there are no nodes.
+pub fn read_poly(text: &str, nodes: Option<&Nodes>) -> Result<Poly, VoronoiError> {
+    let mut lines = Lines::new(text);
+    let mut poly = Poly {
+        nodes: read_vertices(&mut lines)?,
+        ..Poly::default()
+    };
+    if poly.nodes.points.is_empty() {
+        poly.nodes = nodes
+            .cloned()
+            .ok_or_else(|| VoronoiError::InvalidTriangleFile("the .poly file's vertices are in a .node file".to_string()))?;
+    }
+
+    poly.segments = read_edges(&mut lines, &poly.nodes)?;
+
+    let header = lines.next_line()?;
+    for _ in 0..header.usize(0)? {
+        let line = lines.next_line()?;
+        poly.holes.push(Point::new(line.f64(1)?, line.f64(2)?));
+    }
+
+    // The regions are optional, and may be left off entirely
+    if let Some(header) = lines.next_optional_line() {
+        for _ in 0..header.usize(0)? {
+            let line = lines.next_line()?;
+            poly.regions.push(Region {
+                point: Point::new(line.f64(1)?, line.f64(2)?),
+                attribute: line.f64(3)?,
+                max_area: line.f64(4)?,
+            });
+        }
+    }
+    lines.end()?;
+
+    Ok(poly)
+}
+
+/// Writes a `.poly` file, with its vertices in it
+pub fn write_poly(poly: &Poly) -> String {
+    let mut text = String::new();
+    write_vertices(&mut text, &poly.nodes);
+    write_edges(&mut text, &poly.segments, poly.nodes.first);
+
+    let _ = writeln!(text, "{}", poly.holes.len());
+    for (i, hole) in poly.holes.iter().enumerate() {
+        let _ = writeln!(text, "{:4}    {}  {}", i + poly.nodes.first, hole.x, hole.y);
+    }
+
+    if !poly.regions.is_empty() {
+        let _ = writeln!(text, "{}", poly.regions.len());
+        for (i, region) in poly.regions.iter().enumerate() {
+            let _ = writeln!(text, "{:4}    {}  {}  {}  {}", i + poly.nodes.first, region.point.x, region.point.y, region.attribute, region.max_area);
+        }
+    }
+
+    text
+}
+
+/// Reads an `.edge` file, for the vertices in a `.node` file
+pub fn read_edge(text: &str, nodes: &Nodes) -> Result<Edges, VoronoiError> {
+    let mut lines = Lines::new(text);
+    let edges = read_edges(&mut lines, nodes)?;
+    lines.end()?;
+
+    Ok(edges)
+}
+
+/// Writes an `.edge` file, numbering vertices from `first`
+pub fn write_edge(edges: &Edges, first: usize) -> String {
+    let mut text = String::new();
+    write_edges(&mut text, edges, first);
+
+    text
+}
+
+/// Reads a `.v.edge` file, for the Voronoi vertices in a `.v.node` file. A ray is written as its
+/// vertex, then -1, then its direction.
+pub fn read_voronoi_edge(text: &str, nodes: &Nodes) -> Result<Vec<VoronoiEdge>, VoronoiError> {
+    let mut lines = Lines::new(text);
+    let header = lines.next_line()?;
+
+    let mut edges = Vec::new();
+    for _ in 0..header.usize(0)? {
+        let line = lines.next_line()?;
+        let start = line.vertex(1, nodes)?;
+
+        if line.token(2)? == "-1" {
+            edges.push(VoronoiEdge::Ray(start, Point::new(line.f64(3)?, line.f64(4)?)));
+        } else {
+            edges.push(VoronoiEdge::Segment(start, line.vertex(2, nodes)?));
+        }
+    }
+    lines.end()?;
+
+    Ok(edges)
+}
+
+/// Writes a `.v.edge` file, numbering vertices from `first`
+pub fn write_voronoi_edge(edges: &[VoronoiEdge], first: usize) -> String {
+    let mut text = format!("{}  0\n", edges.len());
+
+    for (i, edge) in edges.iter().enumerate() {
+        let _ = match *edge {
+            VoronoiEdge::Segment(a, b) => writeln!(text, "{:4}   {}  {}", i + first, a + first, b + first),
+            VoronoiEdge::Ray(a, direction) => writeln!(text, "{:4}   {}  -1  {}  {}", i + first, a + first, direction.x, direction.y),
+        };
+    }
+
+    text
+}
+
+/// Reads a list of vertices, with its line of counts
+fn read_vertices(lines: &mut Lines) -> Result<Nodes, VoronoiError> {
+    let header = lines.next_line()?;
+    let count = header.usize(0)?;
+    let dimension = header.optional_usize(1, 2)?;
+    let attributes = header.optional_usize(2, 0)?;
+    let markers = header.optional_usize(3, 0)? > 0;
+    if dimension != 2 {
+        return Err(header.error(&format!("vertices have {} coordinates, not 2", dimension)));
+    }
+
+    let mut nodes = Nodes {
+        markers: if markers { Some(Vec::new()) } else { None },
+        ..Nodes::default()
+    };
+    for i in 0..count {
+        let line = lines.next_line()?;
+
+        // The first vertex decides how all of them are numbered
+        if i == 0 {
+            nodes.first = line.usize(0)?;
+        }
+
+        nodes.points.push(Point::new(line.f64(1)?, line.f64(2)?));
+        nodes.attributes.push((0..attributes).map(|a| line.f64(3 + a)).collect::<Result<_, _>>()?);
+        if let Some(markers) = nodes.markers.as_mut() {
+            markers.push(line.i32(3 + attributes)?);
+        }
+    }
+
+    Ok(nodes)
+}
+
+fn write_vertices(text: &mut String, nodes: &Nodes) {
+    let attributes = nodes.attributes.first().map_or(0, Vec::len);
+    let _ = writeln!(text, "{}  2  {}  {}", nodes.points.len(), attributes, nodes.markers.is_some() as usize);
+
+    for (i, p) in nodes.points.iter().enumerate() {
+        let _ = write!(text, "{:4}    {}  {}", i + nodes.first, p.x, p.y);
+        for attribute in nodes.attributes.get(i).into_iter().flatten() {
+            let _ = write!(text, "  {}", attribute);
+        }
+        if let Some(marker) = nodes.markers.as_ref().and_then(|markers| markers.get(i)) {
+            let _ = write!(text, "    {}", marker);
+        }
+        text.push('\n');
+    }
+}
+
+/// Reads a list of edges (or segments), with its line of counts
+fn read_edges(lines: &mut Lines, nodes: &Nodes) -> Result<Edges, VoronoiError> {
+    let header = lines.next_line()?;
+    let count = header.usize(0)?;
+    let markers = header.optional_usize(1, 0)? > 0;
+
+    let mut edges = Edges {
+        edges: Vec::new(),
+        markers: if markers { Some(Vec::new()) } else { None },
+    };
+    for _ in 0..count {
+        let line = lines.next_line()?;
+        edges.edges.push([line.vertex(1, nodes)?, line.vertex(2, nodes)?]);
+        if let Some(markers) = edges.markers.as_mut() {
+            markers.push(line.i32(3)?);
+        }
+    }
+
+    Ok(edges)
+}
+
+fn write_edges(text: &mut String, edges: &Edges, first: usize) {
+    let _ = writeln!(text, "{}  {}", edges.edges.len(), edges.markers.is_some() as usize);
+
+    for (i, edge) in edges.edges.iter().enumerate() {
+        let _ = write!(text, "{:4}   {}  {}", i + first, edge[0] + first, edge[1] + first);
+        if let Some(marker) = edges.markers.as_ref().and_then(|markers| markers.get(i)) {
+            let _ = write!(text, "  {}", marker);
+        }
+        text.push('\n');
+    }
+}
+
+/// The lines of a file that have something on them, split into tokens, with comments removed
+struct Lines<'a> {
+    lines: std::iter::Enumerate<std::str::Lines<'a>>,
+}
+
+/// The tokens on one line, and its line number for errors
+struct Line<'a> {
+    number: usize,
+    tokens: Vec<&'a str>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(text: &'a str) -> Lines<'a> {
+        Lines { lines: text.lines().enumerate() }
+    }
+
+    fn next_optional_line(&mut self) -> Option<Line<'a>> {
+        for (i, line) in self.lines.by_ref() {
+            let content = line.split('#').next().unwrap_or("");
+            let tokens: Vec<&str> = content.split_whitespace().collect();
+            if !tokens.is_empty() {
+                return Some(Line { number: i + 1, tokens });
+            }
+        }
+
+        None
+    }
+
+    fn next_line(&mut self) -> Result<Line<'a>, VoronoiError> {
+        self.next_optional_line()
+            .ok_or_else(|| VoronoiError::InvalidTriangleFile("the file ends too soon".to_string()))
+    }
+
+    /// Checks that nothing is left but comments and blank lines
+    fn end(&mut self) -> Result<(), VoronoiError> {
+        match self.next_optional_line() {
+            Some(line) => Err(line.error("there is more than the counts say there should be")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Line<'a> {
+    fn error(&self, message: &str) -> VoronoiError {
+        VoronoiError::InvalidTriangleFile(format!("line {}: {}", self.number, message))
+    }
+
+    fn token(&self, i: usize) -> Result<&'a str, VoronoiError> {
+        self.tokens.get(i).copied().ok_or_else(|| self.error(&format!("expected at least {} numbers", i.saturating_add(1))))
+    }
+
+    fn parse<T: std::str::FromStr>(&self, i: usize) -> Result<T, VoronoiError> {
+        let token = self.token(i)?;
+        token.parse().map_err(|_| self.error(&format!("{} is not a valid number here", token)))
+    }
+
+    fn f64(&self, i: usize) -> Result<f64, VoronoiError> {
+        self.parse(i)
+    }
+
+    fn i32(&self, i: usize) -> Result<i32, VoronoiError> {
+        self.parse(i)
+    }
+
+    fn usize(&self, i: usize) -> Result<usize, VoronoiError> {
+        self.parse(i)
+    }
+
+    /// A count in a line of counts that can be left off
+    fn optional_usize(&self, i: usize, default: usize) -> Result<usize, VoronoiError> {
+        if i < self.tokens.len() {
+            self.usize(i)
+        } else {
+            Ok(default)
+        }
+    }
+
+    /// The index of a vertex, from its number
+    fn vertex(&self, i: usize, nodes: &Nodes) -> Result<usize, VoronoiError> {
+        let number = self.usize(i)?;
+        number
+            .checked_sub(nodes.first)
+            .filter(|&index| index < nodes.points.len())
+            .ok_or_else(|| self.error(&format!("there is no vertex {}", number)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dulaney::dulaney_triangulation;
+    use crate::generators::{seeded_rng, uniform};
+
+    fn triangulation() -> Triangulation {
+        dulaney_triangulation(&uniform(&mut seeded_rng(1), 30, 0.0..100.0, 0.0..100.0)).unwrap()
+    }
+
+    fn nodes() -> Nodes {
+        Nodes {
+            points: vec![Point::new(0.0, 0.0), Point::new(1.5, -2.25), Point::new(0.1, 1e-7), Point::new(-3.0, 4.0)],
+            attributes: vec![vec![1.0, -0.5], vec![2.0, 0.0], vec![3.0, 1e10], vec![4.0, 0.1]],
+            markers: Some(vec![1, 0, -2, 7]),
+            first: 1,
+        }
+    }
+
+    /// Checks that reading fails with an error naming the problem
+    fn assert_invalid<T: std::fmt::Debug>(result: Result<T, VoronoiError>, message: &str) {
+        match result {
+            Err(VoronoiError::InvalidTriangleFile(error)) => assert!(error.contains(message), "{:?} doesn't say {:?}", error, message),
+            other => panic!("expected an error saying {:?}, got {:?}", message, other),
+        }
+    }
+
+    #[test]
+    fn node_round_trip() {
+        let nodes = nodes();
+        assert_eq!(read_node(&write_node(&nodes)).unwrap(), nodes);
+
+        let plain = Nodes {
+            attributes: vec![Vec::new(); 4],
+            markers: None,
+            first: 0,
+            ..nodes
+        };
+        assert_eq!(read_node(&write_node(&plain)).unwrap(), plain);
+
+        let from_triangulation = Nodes::from_triangulation(&triangulation());
+        assert_eq!(read_node(&write_node(&from_triangulation)).unwrap(), from_triangulation);
+    }
+
+    #[test]
+    fn ele_round_trip() {
+        let triangulation = triangulation();
+        let points = Nodes::from_triangulation(&triangulation);
+        let elements = Elements::from_triangulation(&triangulation);
+
+        let read = read_ele(&write_ele(&elements, points.first), &points).unwrap();
+        assert_eq!(read, elements);
+        assert_eq!(read.to_triangulation(&points).unwrap(), triangulation);
+
+        let elements = Elements {
+            triangles: vec![[0, 1, 2], [0, 2, 3]],
+            attributes: vec![vec![0.5], vec![-1.0]],
+        };
+        for first in 0..2 {
+            let nodes = Nodes { first, ..nodes() };
+            assert_eq!(read_ele(&write_ele(&elements, first), &nodes).unwrap(), elements);
+        }
+    }
+
+    #[test]
+    fn poly_round_trip() {
+        let mut poly = Poly {
+            nodes: nodes(),
+            segments: Edges {
+                edges: vec![[0, 1], [1, 3], [3, 0]],
+                markers: Some(vec![1, 1, 2]),
+            },
+            holes: vec![Point::new(0.25, 0.5)],
+            regions: vec![Region {
+                point: Point::new(-1.0, 1.0),
+                attribute: 3.0,
+                max_area: -1.0,
+            }],
+        };
+        assert_eq!(read_poly(&write_poly(&poly), None).unwrap(), poly);
+
+        // Regions can be left off
+        poly.regions.clear();
+        poly.segments.markers = None;
+        assert_eq!(read_poly(&write_poly(&poly), None).unwrap(), poly);
+
+        // The vertices can come from a .node file instead
+        let text = "0 2 0 0\n1 0\n1 1 2\n0\n";
+        let poly = read_poly(text, Some(&nodes())).unwrap();
+        assert_eq!(poly.nodes, nodes());
+        assert_eq!(poly.segments.edges, vec![[0, 1]]);
+    }
+
+    #[test]
+    fn edge_round_trip() {
+        let triangulation = triangulation();
+        let nodes = Nodes::from_triangulation(&triangulation);
+        let edges = Edges::from_triangulation(&triangulation);
+        assert_eq!(read_edge(&write_edge(&edges, nodes.first), &nodes).unwrap(), edges);
+
+        // Every edge once, and only the hull edges are marked
+        let mesh = &triangulation.mesh;
+        assert_eq!(edges.edges.len() * 2, mesh.edge_count());
+        let hull = mesh.edges().filter(|&edge| mesh.face(edge).is_none()).count();
+        assert_eq!(edges.markers.unwrap().iter().filter(|&&marker| marker == 1).count(), hull);
+    }
+
+    #[test]
+    fn voronoi_round_trip() {
+        let triangulation = triangulation();
+        let (nodes, edges) = voronoi_from_triangulation(&triangulation);
+        assert_eq!(read_node(&write_node(&nodes)).unwrap(), nodes);
+        assert_eq!(read_voronoi_edge(&write_voronoi_edge(&edges, nodes.first), &nodes).unwrap(), edges);
+
+        // A segment for each inside edge, and a ray for each hull edge
+        let mesh = &triangulation.mesh;
+        let rays = edges.iter().filter(|edge| matches!(edge, VoronoiEdge::Ray(..))).count();
+        let hull = mesh.edges().filter(|&edge| mesh.face(edge).is_none()).count();
+        assert_eq!(rays, hull);
+        assert_eq!(edges.len(), mesh.edge_count() / 2);
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        let text = "# A square\n\n4 2 0 1  # counts\n1 0 0 1\n\n2 1 0 1\n3 1 1 1 # corner\n4 0 1 1\n# the end\n";
+        let nodes = read_node(text).unwrap();
+        assert_eq!(nodes.points.len(), 4);
+        assert_eq!(nodes.markers, Some(vec![1; 4]));
+    }
+
+    #[test]
+    fn wrong_counts() {
+        assert_invalid(read_node(""), "ends too soon");
+        assert_invalid(read_node("3 2 0 0\n1 0 0\n2 1 0\n"), "ends too soon");
+        assert_invalid(read_node("1 2 0 0\n1 0 0\n2 1 0\n"), "line 3: there is more");
+        assert_invalid(read_node("-1 2 0 0\n"), "-1 is not a valid number");
+        assert_invalid(read_node("1 3 0 0\n1 0 0 0\n"), "3 coordinates");
+        assert_invalid(read_ele("1 2 0\n1 1 2\n", &nodes()), "can't have 2 corners");
+        assert_invalid(read_ele("18446744073709551615 3 0\n1 1 2 3\n", &nodes()), "ends too soon");
+        assert_invalid(read_edge("18446744073709551615 0\n1 1 2\n", &nodes()), "ends too soon");
+        assert_invalid(read_poly("4 2 0 0\n1 0 0\n2 1 0\n3 1 1\n4 0 1\n1 0\n1 1 2\n", None), "ends too soon");
+        assert_invalid(read_voronoi_edge("2 0\n1 1 2\n", &nodes()), "ends too soon");
+    }
+
+    #[test]
+    fn bad_indices() {
+        // Numbered from 1, so 0 and 5 are out of range
+        assert_invalid(read_ele("1 3 0\n1 0 1 2\n", &nodes()), "no vertex 0");
+        assert_invalid(read_ele("1 3 0\n1 1 2 5\n", &nodes()), "no vertex 5");
+        assert_invalid(read_edge("1 0\n1 4 5\n", &nodes()), "no vertex 5");
+        assert_invalid(read_edge("1 0\n1 1 -2\n", &nodes()), "-2 is not a valid number");
+        assert_invalid(read_voronoi_edge("1 0\n1 5 -1 1 0\n", &nodes()), "no vertex 5");
+        assert_invalid(read_poly("0 2 0 0\n1 0\n1 1 9\n0\n", Some(&nodes())), "no vertex 9");
+        assert_invalid(read_poly("0 2 0 0\n0 0\n0\n", None), "in a .node file");
+
+        // Indices in range, but not a triangulation
+        let elements = read_ele("1 3 0\n1 1 2 1\n", &nodes()).unwrap();
+        assert!(elements.to_triangulation(&nodes()).is_err());
+    }
+
+    #[test]
+    fn missing_values() {
+        assert_invalid(read_node("1 2 2 0\n1 0 0 5\n"), "expected at least 5 numbers");
+        assert_invalid(read_node("1 2 0 1\n1 0 0\n"), "expected at least 4 numbers");
+        assert_invalid(read_node("1 2 0 0\n1 0\n"), "expected at least 3 numbers");
+        assert_invalid(read_node("1 2 0 0\n1 0 north\n"), "north is not a valid number");
+        assert_invalid(read_ele("1 3 1\n1 1 2 3\n", &nodes()), "expected at least 5 numbers");
+        assert_invalid(read_ele("1 18446744073709551615 1\n1 1 2 3 4\n", &nodes()), "numbers");
+        assert_invalid(read_edge("1 1\n1 1 2\n", &nodes()), "expected at least 4 numbers");
+        assert_invalid(read_voronoi_edge("1 0\n1 1 -1 0.5\n", &nodes()), "expected at least 5 numbers");
+        assert_invalid(read_poly("0 2 0 0\n0 0\n1\n1 0.5\n", Some(&nodes())), "expected at least 3 numbers");
+        assert_invalid(read_poly("0 2 0 0\n0 0\n0\n1\n1 0 0 1\n", Some(&nodes())), "expected at least 5 numbers");
+    }
+}
@@ -0,0 +1,244 @@
+//!
+//! The Voronoi diagram of a set of sites, constructed as the dual of the Dulaney triangulation.
+//! Every triangle in the triangulation becomes a vertex of the diagram (its circumcenter), and the
+//! cell of each site is the polygon formed by the circumcenters of the triangles around that site.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::dulaney::{dulaney_triangulation, Triangulation};
+use crate::error::VoronoiError;
+use crate::mesh::{FaceId, VertexId};
+use crate::point::Point;
+use crate::polygon::{clip_half_plane, ConvexPolygon};
+
+/// A single Voronoi region, which contains every point closer to its site than to any other site.
+///
+/// Vertices are ordered counter-clockwise (in a y-up coordinate system, so they will appear
+/// clockwise on a canvas). `neighbors[i]` is the site on the other side of the edge that ends at
+/// `vertices[i]`. Cells of sites on the convex hull are unbounded: their vertices form an open
+/// chain, the first vertex is reached by a ray coming in from infinity, and the last vertex leaves
+/// along another ray. These cells have one more neighbor than vertices, the last of which is across
+/// the outgoing ray.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    /// Index of the site this cell belongs to
+    pub site: usize,
+
+    pub vertices: Vec<Point>,
+    pub neighbors: Vec<usize>,
+
+    /// False if the cell extends out to infinity
+    pub bounded: bool,
+
+    /// For unbounded cells, the directions of the incoming ray (pointing away from the first
+    /// vertex) and the outgoing ray (pointing away from the last vertex).
+    pub rays: Option<(Point, Point)>,
+}
+
+/// The Voronoi diagram, along with the triangulation it is the dual of. Vertex i of the
+/// triangulation's mesh is sites[i], and the vertices of the diagram are the circumcenters of its
+/// faces, so the mesh can be used to move between neighboring cells.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoronoiDiagram {
+    pub sites: Vec<Point>,
+    pub cells: Vec<Cell>,
+    pub triangulation: Triangulation,
+}
+
+impl Cell {
+    /// An empty cell, used for sites that are not part of any triangle
+    pub(crate) fn empty(site: usize) -> Cell {
+        Cell {
+            site,
+            vertices: Vec::new(),
+            neighbors: Vec::new(),
+            bounded: false,
+            rays: None,
+        }
+    }
+}
+
+impl VoronoiDiagram {
+    /// Triangulates the sites and builds the Voronoi diagram from the result. See
+    /// `dulaney_triangulation` for the ways this can fail.
+    pub fn new(sites: &[Point]) -> Result<VoronoiDiagram, VoronoiError> {
+        let triangulation = dulaney_triangulation(sites)?;
+        Ok(Self::from_triangulation(triangulation))
+    }
+
+    /// Builds the Voronoi diagram for the points of a Dulaney triangulation.
+    pub fn from_triangulation(triangulation: Triangulation) -> VoronoiDiagram {
+        let cells = triangulation
+            .mesh
+            .vertices()
+            .map(|vertex| cell(&triangulation, vertex, |face| triangulation.triangle(face).circumcenter))
+            .collect();
+
+        VoronoiDiagram {
+            sites: triangulation.points().collect(),
+            cells,
+            triangulation,
+        }
+    }
+
+    /// The sites whose cells share an edge with the cell of a site
+    pub fn neighbors(&self, site: usize) -> impl Iterator<Item = usize> + '_ {
+        self.triangulation.mesh.vertex_neighbors(VertexId(site)).map(|vertex| vertex.0)
+    }
+
+    /// Clips the cell of every site to a convex region, giving one polygon per site (in the same
+    /// order as the sites). Each polygon is closed, with its vertices counter-clockwise and the
+    /// first vertex not repeated at the end. Cells entirely outside the region are empty.
+    pub fn clip(&self, region: &ConvexPolygon) -> Vec<Vec<Point>> {
+        (0..self.sites.len()).map(|site| self.clip_cell(site, region)).collect()
+    }
+
+    /// Clips the cell of a single site to a convex region. See `clip`.
+    pub fn clip_cell(&self, site: usize, region: &ConvexPolygon) -> Vec<Point> {
+        let s = self.sites[site];
+        let tolerance = 1e-10 * region.extent();
+
+        // The cell is every point at least as close to the site as to each of its neighbors, so it
+        // can be cut out of the region one bisector at a time. Unbounded cells need no special
+        // treatment this way.
+        let mut polygon = region.vertices().to_vec();
+        for &neighbor in self.cells[site].neighbors.iter() {
+            let n = self.sites[neighbor];
+            let (mx, my) = ((s.x + n.x) / 2.0, (s.y + n.y) / 2.0);
+            let (dx, dy) = (n.x - s.x, n.y - s.y);
+
+            polygon = clip_half_plane(&polygon, |p| (p.x - mx) * dx + (p.y - my) * dy, tolerance);
+            if polygon.is_empty() {
+                break;
+            }
+        }
+
+        polygon
+    }
+}
+
+/// Walks counter-clockwise around a site, collecting the centers of its triangles: the point the
+/// cell's sites are all equally far from, under whichever distance the diagram uses.
+pub(crate) fn cell<F: Fn(FaceId) -> Point>(triangulation: &Triangulation, vertex: VertexId, center: F) -> Cell {
+    let mesh = &triangulation.mesh;
+    let site = vertex.0;
+
+    // Each outgoing edge has the next triangle around the site on its left. On the convex
+    // hull, the walk starts from the edge with no triangle, which is the last neighbor.
+    let mut vertices = Vec::new();
+    let mut neighbors = Vec::new();
+    let mut hull = None;
+    for edge in mesh.edges_around_vertex(vertex) {
+        let neighbor = mesh.destination(edge).0;
+
+        match mesh.face(edge) {
+            Some(face) => {
+                neighbors.push(neighbor);
+                vertices.push(center(face));
+            }
+            None => hull = Some(neighbor),
+        }
+    }
+
+    if vertices.is_empty() {
+        return Cell::empty(site);
+    }
+
+    // Rays run perpendicular to the hull edges, away from the triangles.
+    let rays = hull.map(|last| {
+        neighbors.push(last);
+
+        let v = mesh.point(vertex);
+        let (a, b) = (mesh.point(VertexId(neighbors[0])), mesh.point(VertexId(last)));
+
+        (
+            Point::new(a.y - v.y, v.x - a.x),
+            Point::new(v.y - b.y, b.x - v.x),
+        )
+    });
+
+    Cell {
+        site,
+        vertices,
+        neighbors,
+        bounded: rays.is_none(),
+        rays,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{seeded_rng, uniform};
+    use crate::metric::Euclidean;
+    use crate::polygon::{area, contains};
+
+    fn assert_tiles(diagram: &VoronoiDiagram, region: &ConvexPolygon) {
+        let cells = diagram.clip(region);
+        assert_eq!(cells.len(), diagram.sites.len());
+
+        let mut total = 0.0;
+        for (site, cell) in cells.iter().enumerate() {
+            let s = diagram.sites[site];
+            if region.contains(s) {
+                assert!(contains(cell, s), "site {} is outside its cell", site);
+            }
+            if !cell.is_empty() {
+                assert!(area(cell) > 0.0, "cell {} isn't counter-clockwise", site);
+            }
+            total += area(cell);
+        }
+
+        assert!((total - region.area()).abs() < 1e-9 * region.area(), "cells cover {} of {}", total, region.area());
+    }
+
+    #[test]
+    fn clipped_cells_tile_the_region() {
+        for seed in 0..20 {
+            let sites = uniform(&mut seeded_rng(seed), 50, 0.0..100.0, 0.0..60.0);
+            let diagram = VoronoiDiagram::new(&sites).unwrap();
+
+            // Around all of the sites, and through the middle of them
+            assert_tiles(&diagram, &ConvexPolygon::rectangle(-10.0, -10.0, 110.0, 70.0).unwrap());
+            assert_tiles(&diagram, &ConvexPolygon::rectangle(20.0, 10.0, 70.0, 40.0).unwrap());
+        }
+    }
+
+    #[test]
+    fn rays_point_away_from_the_hull() {
+        for seed in 0..20 {
+            let sites = uniform(&mut seeded_rng(seed), 30, 0.0..100.0, 0.0..100.0);
+            let diagram = VoronoiDiagram::new(&sites).unwrap();
+
+            for cell in diagram.cells.iter() {
+                let (incoming, outgoing) = match cell.rays {
+                    Some(rays) => rays,
+                    None => continue,
+                };
+                assert!(!cell.bounded);
+
+                let s = diagram.sites[cell.site];
+                let ends = [
+                    (cell.vertices[0], incoming, cell.neighbors[0]),
+                    (cell.vertices[cell.vertices.len() - 1], outgoing, cell.neighbors[cell.neighbors.len() - 1]),
+                ];
+                for &(start, ray, neighbor) in ends.iter() {
+                    // No site is on the far side of the hull edge the ray crosses
+                    for p in diagram.sites.iter() {
+                        assert!((p.x - s.x) * ray.x + (p.y - s.y) * ray.y <= 1e-9);
+                    }
+
+                    // Far along the ray, the site and its neighbor are still the nearest
+                    let far = Point::new(start.x + 1e3 * ray.x, start.y + 1e3 * ray.y);
+                    let to_site = s.dist(&far, &Euclidean);
+                    let to_neighbor = diagram.sites[neighbor].dist(&far, &Euclidean);
+                    assert!((to_site - to_neighbor).abs() < 1e-6 * to_site);
+                    for p in diagram.sites.iter() {
+                        assert!(p.dist(&far, &Euclidean) >= to_site * (1.0 - 1e-9));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,335 @@
+//!
+//! Worley (cellular) noise: the distance from a point to the nearest of a scattering of sites, or
+//! to the second nearest, and so on. F1 (the nearest) looks like a field of cones, one per Voronoi
+//! cell, and F2 - F1 is zero along the Voronoi edges, which makes it look like cracks or cells.
+//!
+//! The sites come from a jittered grid: every unit square cell has the same number of sites,
+//! placed at random inside it by hashing the cell's coordinates with the seed. Nothing is stored,
+//! so the noise covers the whole plane, and evaluating it only needs to look at the few cells
+//! around the point.
+//!
+//! https://bren.app/voronoi/
+//!
+
+use crate::metric::Metric;
+use crate::point::Point;
+
+/// How many rings of cells around the point have their bounds worked out up front
+const CACHED_RINGS: usize = 8;
+
+/// Which distance to sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// The distance to the nearest site
+    F1,
+
+    /// The distance to the second nearest site
+    F2,
+
+    /// The difference between the distances to the second nearest and nearest sites
+    F2MinusF1,
+
+    /// The distance to the n-th nearest site, counting from 1. The 0th is the point itself, at a
+    /// distance of 0.
+    Nth(usize),
+}
+
+/// Cellular noise, with distances measured by a metric. The metric has to be convex (true of every
+/// metric in `crate::metric` except Minkowski with p below 1) and only depend on the difference
+/// between the points, so that the search can tell when no further cell can hold a nearer site.
+#[derive(Debug, Clone)]
+pub struct Worley<M: Metric> {
+    pub seed: u64,
+
+    /// How far sites stray from the centers of their cells, from 0 (a regular grid) to 1 (anywhere
+    /// in the cell). Values outside that range are clamped.
+    pub jitter: f64,
+
+    /// How many sites each cell has, at least 1
+    pub points_per_cell: usize,
+
+    /// If there is one, the number of cells across and down after which the noise repeats, so it
+    /// tiles seamlessly
+    pub period: Option<(u32, u32)>,
+
+    metric: M,
+
+    /// The smallest distance from the origin to the boundary of the square of each radius, so
+    /// that `ring_bounds[r]` is a lower bound on the distance to any site more than r rings of
+    /// cells away
+    ring_bounds: Vec<f64>,
+}
+
+impl<M: Metric> Worley<M> {
+    /// Noise with one site anywhere in each cell, that doesn't repeat
+    pub fn new(seed: u64, metric: M) -> Worley<M> {
+        let ring_bounds = (0..=CACHED_RINGS).map(|r| ring_bound(&metric, r as f64)).collect();
+
+        Worley {
+            seed,
+            jitter: 1.0,
+            points_per_cell: 1,
+            period: None,
+            metric,
+            ring_bounds,
+        }
+    }
+
+    pub fn metric(&self) -> &M {
+        &self.metric
+    }
+
+    /// The sites in cell (x, y), which is the unit square with its lowest corner at (x, y)
+    pub fn sites(&self, x: i64, y: i64) -> impl Iterator<Item = Point> + '_ {
+        self.sites_with_seed(self.seed, x, y)
+    }
+
+    /// The distances from the point to its n nearest sites, nearest first
+    pub fn distances(&self, p: Point, n: usize) -> Vec<f64> {
+        self.distances_with_seed(self.seed, p, n)
+    }
+
+    /// One feature of the noise at the point
+    pub fn sample(&self, p: Point, feature: Feature) -> f64 {
+        self.sample_with_seed(self.seed, p, feature)
+    }
+
+    /// Fractal noise: the sum of `octaves` layers of noise, each `lacunarity` times the frequency
+    /// and `gain` times the amplitude of the one before, divided by the total amplitude so that it
+    /// stays in the same range as a single layer. Each layer has its own sites. Tileable noise
+    /// stays tileable as long as the lacunarity is a whole number.
+    pub fn fractal(&self, p: Point, feature: Feature, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+        let (mut total, mut amplitudes) = (0.0, 0.0);
+        let (mut frequency, mut amplitude) = (1.0, 1.0);
+
+        for octave in 0..octaves {
+            let seed = self.seed.wrapping_add(octave.into());
+            total += amplitude * self.sample_with_seed(seed, Point::new(p.x * frequency, p.y * frequency), feature);
+            amplitudes += amplitude;
+
+            frequency *= lacunarity;
+            amplitude *= gain;
+        }
+
+        if amplitudes == 0.0 {
+            0.0
+        } else {
+            total / amplitudes
+        }
+    }
+
+    fn sample_with_seed(&self, seed: u64, p: Point, feature: Feature) -> f64 {
+        match feature {
+            Feature::F1 => self.distances_with_seed(seed, p, 1)[0],
+            Feature::F2 => self.distances_with_seed(seed, p, 2)[1],
+            Feature::F2MinusF1 => {
+                let distances = self.distances_with_seed(seed, p, 2);
+                distances[1] - distances[0]
+            }
+            Feature::Nth(0) => 0.0,
+            Feature::Nth(n) => self.distances_with_seed(seed, p, n)[n - 1],
+        }
+    }
+
+    fn sites_with_seed(&self, seed: u64, x: i64, y: i64) -> impl Iterator<Item = Point> + '_ {
+        // Tileable noise uses the same sites in every period, moved along with the cell
+        let (hash_x, hash_y) = match self.period {
+            Some((width, height)) => (x.rem_euclid(width.max(1).into()), y.rem_euclid(height.max(1).into())),
+            None => (x, y),
+        };
+        let jitter = self.jitter.clamp(0.0, 1.0);
+
+        (0..self.points_per_cell.max(1) as u64).map(move |k| {
+            let u = unit(hash(seed, hash_x, hash_y, 2 * k));
+            let v = unit(hash(seed, hash_x, hash_y, 2 * k + 1));
+
+            Point::new(x as f64 + 0.5 + jitter * (u - 0.5), y as f64 + 0.5 + jitter * (v - 0.5))
+        })
+    }
+
+    fn distances_with_seed(&self, seed: u64, p: Point, n: usize) -> Vec<f64> {
+        if n == 0 || !p.x.is_finite() || !p.y.is_finite() {
+            return vec![f64::NAN; n];
+        }
+
+        let mut nearest: Vec<f64> = Vec::with_capacity(n + 1);
+
+        let (cell_x, cell_y) = (p.x.floor() as i64, p.y.floor() as i64);
+        let visit = |nearest: &mut Vec<f64>, x: i64, y: i64| {
+            for site in self.sites_with_seed(seed, x, y) {
+                let d = self.metric.distance(p, site);
+                if d.is_nan() || (nearest.len() == n && d >= nearest[n - 1]) {
+                    continue;
+                }
+
+                let at = nearest.partition_point(|&other| other <= d);
+                nearest.insert(at, d);
+                nearest.truncate(n);
+            }
+        };
+
+        // Search square rings of cells outwards, until the n-th nearest site so far is nearer
+        // than anything in the next ring could be. Every site stays inside its own cell, so a
+        // site more than r rings out is at least r away along one axis or the other.
+        visit(&mut nearest, cell_x, cell_y);
+        let mut ring = 1;
+        loop {
+            let r = ring as i64;
+            for i in -r..r {
+                visit(&mut nearest, cell_x + i, cell_y - r);
+                visit(&mut nearest, cell_x + r, cell_y + i);
+                visit(&mut nearest, cell_x - i, cell_y + r);
+                visit(&mut nearest, cell_x - r, cell_y - i);
+            }
+
+            if nearest.len() == n && nearest[n - 1] <= self.ring_bound(ring) {
+                break;
+            }
+
+            // A metric that only gives NaN would never find anything
+            if nearest.is_empty() && ring >= CACHED_RINGS {
+                break;
+            }
+            ring += 1;
+        }
+
+        nearest.resize(n, f64::NAN);
+        nearest
+    }
+
+    /// A lower bound on the distance to any site more than `ring` rings of cells away
+    fn ring_bound(&self, ring: usize) -> f64 {
+        match self.ring_bounds.get(ring) {
+            Some(&bound) => bound,
+            None => ring_bound(&self.metric, ring as f64),
+        }
+    }
+}
+
+/// The smallest distance from the origin to the boundary of the axis-aligned square with the given
+/// radius (half its side). The distance is convex along each side, so a ternary search finds the
+/// smallest value on it, which is then shaded down slightly to make up for rounding.
+fn ring_bound<M: Metric + ?Sized>(metric: &M, radius: f64) -> f64 {
+    let origin = Point::new(0.0, 0.0);
+    let smallest_along = |side: &dyn Fn(f64) -> Point| {
+        let (mut low, mut high) = (-radius, radius);
+        for _ in 0..100 {
+            let (a, b) = (low + (high - low) / 3.0, high - (high - low) / 3.0);
+            if metric.distance(origin, side(a)) < metric.distance(origin, side(b)) {
+                high = b;
+            } else {
+                low = a;
+            }
+        }
+
+        metric.distance(origin, side((low + high) / 2.0))
+    };
+
+    // Metrics are symmetric, so the other two sides are the same as these
+    let right = smallest_along(&|t| Point::new(radius, t));
+    let top = smallest_along(&|t| Point::new(t, radius));
+
+    right.min(top) * (1.0 - 1e-9)
+}
+
+/// Hashes a cell and an index within it, by mixing them into the seed with the finalizer from
+/// SplitMix64
+fn hash(seed: u64, x: i64, y: i64, k: u64) -> u64 {
+    let mut h = seed
+        ^ (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+        ^ k.wrapping_mul(0x1656_67b1_9e37_79f9);
+
+    for _ in 0..2 {
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^= h >> 31;
+    }
+
+    h
+}
+
+/// Turns a hash into a number from 0 (inclusive) to 1 (exclusive)
+fn unit(hash: u64) -> f64 {
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::{seeded_rng, uniform};
+    use crate::metric::{Chebyshev, Euclidean, Manhattan};
+
+    fn points() -> Vec<Point> {
+        uniform(&mut seeded_rng(5), 200, -10.0..10.0, -10.0..10.0)
+    }
+
+    /// The distances to the n nearest sites, from every site in a wide square of cells around p
+    fn brute_force<M: Metric>(noise: &Worley<M>, p: Point, n: usize) -> Vec<f64> {
+        let (x, y) = (p.x.floor() as i64, p.y.floor() as i64);
+        let mut distances: Vec<f64> = (x - 6..=x + 6)
+            .flat_map(|cx| (y - 6..=y + 6).map(move |cy| (cx, cy)))
+            .flat_map(|(cx, cy)| noise.sites(cx, cy).collect::<Vec<_>>())
+            .map(|site| noise.metric().distance(p, site))
+            .collect();
+        distances.sort_by(|a, b| a.total_cmp(b));
+        distances.truncate(n);
+
+        distances
+    }
+
+    #[test]
+    fn same_seed_same_noise() {
+        let (a, b, c) = (Worley::new(11, Euclidean), Worley::new(11, Euclidean), Worley::new(12, Euclidean));
+
+        let mut differs = false;
+        for p in points() {
+            assert_eq!(a.sample(p, Feature::F1), b.sample(p, Feature::F1));
+            assert_eq!(a.fractal(p, Feature::F2, 3, 2.0, 0.5), b.fractal(p, Feature::F2, 3, 2.0, 0.5));
+            differs |= a.sample(p, Feature::F1) != c.sample(p, Feature::F1);
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn features_are_ordered() {
+        let noise = Worley::new(3, Euclidean);
+
+        for p in points() {
+            let (f1, f2) = (noise.sample(p, Feature::F1), noise.sample(p, Feature::F2));
+            assert!(0.0 <= f1 && f1 <= f2);
+            assert_eq!(noise.sample(p, Feature::F2MinusF1), f2 - f1);
+            assert_eq!(noise.sample(p, Feature::Nth(1)), f1);
+            assert_eq!(noise.sample(p, Feature::Nth(0)), 0.0);
+
+            let distances = noise.distances(p, 5);
+            assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut euclidean = Worley::new(1, Euclidean);
+        euclidean.points_per_cell = 3;
+        let mut manhattan = Worley::new(2, Manhattan);
+        manhattan.jitter = 0.5;
+        let chebyshev = Worley::new(3, Chebyshev);
+
+        for p in points() {
+            assert_eq!(euclidean.distances(p, 4), brute_force(&euclidean, p, 4));
+            assert_eq!(manhattan.distances(p, 4), brute_force(&manhattan, p, 4));
+            assert_eq!(chebyshev.distances(p, 4), brute_force(&chebyshev, p, 4));
+        }
+    }
+
+    #[test]
+    fn tiles() {
+        let mut noise = Worley::new(9, Euclidean);
+        noise.period = Some((4, 3));
+
+        for p in points() {
+            let moved = Point::new(p.x + 4.0, p.y - 6.0);
+            assert!((noise.sample(p, Feature::F1) - noise.sample(moved, Feature::F1)).abs() < 1e-9);
+        }
+    }
+}